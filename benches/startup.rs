@@ -0,0 +1,25 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+const JAVA_HOME_OUTPUT: &[u8] = include_bytes!("fixtures/java_home.plist");
+
+fn list(c: &mut Criterion) {
+    c.bench_function("parse and list", |b| {
+        b.iter(|| {
+            let jvms = jvmvj::parse_jvms(black_box(JAVA_HOME_OUTPUT));
+            jvmvj::render_jvm_list(&jvms)
+        })
+    });
+}
+
+fn resolve(c: &mut Criterion) {
+    c.bench_function("parse and resolve temurin-17", |b| {
+        b.iter(|| {
+            let jvms = jvmvj::parse_jvms(black_box(JAVA_HOME_OUTPUT));
+            jvmvj::resolve_spec(black_box("temurin-17"), &jvms).is_some()
+        })
+    });
+}
+
+criterion_group!(benches, list, resolve);
+criterion_main!(benches);
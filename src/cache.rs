@@ -0,0 +1,222 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{exit_with_err, home_dir, Jvm, Verbosity};
+
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(home_dir()?.join(".cache/jvmvj"))
+}
+
+fn remembered_dirs_path() -> Option<PathBuf> {
+    Some(cache_dir()?.join("dirs.json"))
+}
+
+/// Specs chosen with `use --remember`, keyed by canonical directory path.
+fn load_remembered_dirs() -> BTreeMap<String, String> {
+    remembered_dirs_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn resolutions_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("resolved.json")
+}
+
+/// What `auto` chose, and enough of how it chose it to warn again as it did
+/// the first time.
+#[derive(Deserialize, Serialize)]
+pub struct Choice {
+    pub spec: String,
+    /// The only architecture `.jvmvjrc` allowed, if it named one.
+    pub arch: Option<String>,
+    pub home: String,
+}
+
+/// A JVM that `auto` chose from a version file.
+#[derive(Deserialize, Serialize)]
+struct Resolution {
+    /// The modification time of each input file, or `None` where it didn't
+    /// exist.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    jvms: u64,
+    choice: Choice,
+}
+
+/// Everything a choice by `auto` depends on, so that it is only reused
+/// while none of it changes.
+pub struct Inputs<'a> {
+    pub dir: &'a Path,
+    /// The arguments `auto` was run with, as flags such as `--jdk-only`
+    /// change the choice.
+    pub args: &'a [String],
+    pub config: Option<&'a Path>,
+    /// The environment variables that change the choice, as `NAME=value`.
+    pub env: Vec<String>,
+    /// Every file that could change the choice if it appeared, disappeared
+    /// or was modified: the config, and each version file and `.jvmvjrc` in
+    /// the directories walked.
+    pub files: Vec<PathBuf>,
+    pub jvms: &'a [Jvm],
+}
+
+impl Inputs<'_> {
+    fn key(&self) -> String {
+        let config = self
+            .config
+            .map(|config| config.to_string_lossy())
+            .unwrap_or_default();
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.dir.to_string_lossy(),
+            config,
+            self.env.join(" "),
+            self.args.join(" ")
+        )
+    }
+
+    fn modified(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        self.files
+            .iter()
+            .map(|file| (file.clone(), modified(file)))
+            .collect()
+    }
+
+    /// A hash of the installed JVMs, as installing or removing one can
+    /// change the choice too.
+    fn jvms_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for jvm in self.jvms {
+            (&jvm.home_path, &jvm.version, &jvm.arch, jvm.enabled).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// What `auto` chose, keyed by directory, config, environment and
+/// arguments.
+fn load_resolutions(cache_dir: &Path) -> BTreeMap<String, Resolution> {
+    fs::read_to_string(resolutions_path(cache_dir))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// What `auto` last chose with these inputs, as long as none of them has
+/// changed since.
+pub fn resolved(cache_dir: &Path, inputs: &Inputs) -> Option<Choice> {
+    let resolution = load_resolutions(cache_dir).remove(&inputs.key())?;
+    (resolution.files == inputs.modified()
+        && resolution.jvms == inputs.jvms_fingerprint())
+    .then_some(resolution.choice)
+}
+
+/// Remembers what `auto` chose with these inputs. This is only an
+/// optimisation, so failing to write it is not an error.
+pub fn remember_resolution(cache_dir: &Path, inputs: &Inputs, choice: Choice) {
+    let mut resolutions = load_resolutions(cache_dir);
+    resolutions.insert(
+        inputs.key(),
+        Resolution {
+            files: inputs.modified(),
+            jvms: inputs.jvms_fingerprint(),
+            choice,
+        },
+    );
+    let _ = fs::create_dir_all(cache_dir).and_then(|_| {
+        fs::write(
+            resolutions_path(cache_dir),
+            serde_json::to_string(&resolutions).unwrap(),
+        )
+    });
+}
+
+pub fn remembered_spec(dir: &Path) -> Option<String> {
+    load_remembered_dirs().remove(dir.to_string_lossy().as_ref())
+}
+
+pub fn remember_spec(dir: &Path, spec: &str, verbosity: Verbosity) {
+    let quiet = matches!(verbosity, Verbosity::Quiet | Verbosity::Silent);
+    let Some(path) = remembered_dirs_path() else {
+        if !quiet {
+            eprintln!("Not remembering this choice as HOME is not set");
+        }
+        return;
+    };
+
+    let mut dirs = load_remembered_dirs();
+    dirs.insert(dir.to_string_lossy().into_owned(), spec.to_string());
+
+    let written = fs::create_dir_all(path.parent().unwrap()).and_then(|_| {
+        fs::write(&path, serde_json::to_string_pretty(&dirs).unwrap())
+    });
+    match written {
+        Err(err) if !quiet => eprintln!(
+            "Failed to remember this choice in {}: {}",
+            path.display(),
+            err
+        ),
+        _ => {}
+    }
+}
+
+/// The total size in bytes of the files under `path`.
+fn size_of(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| size_of(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Deletes everything in the cache directory, printing how much was freed.
+pub fn prune(verbosity: Verbosity) {
+    let entries = cache_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect::<Vec<PathBuf>>()
+        })
+        .unwrap_or_default();
+    if entries.is_empty() {
+        println!("nothing to prune");
+        return;
+    }
+
+    let mut freed = 0;
+    for path in entries {
+        let size = size_of(&path);
+        let removed = if path.is_dir() && !path.is_symlink() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(err) = removed {
+            exit_with_err(
+                &format!("Failed to remove {}: {}", path.display(), err),
+                verbosity,
+            );
+        }
+        freed += size;
+    }
+    println!("Freed {} bytes", freed);
+}
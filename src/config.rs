@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::{exit_with_err, home_dir, Verbosity};
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Files checked, in order, in each directory while walking up from the
+    /// current directory. `.tool-versions` is parsed asdf-style and
+    /// `.sdkmanrc` SDKMAN-style; any other file is expected to contain just
+    /// the version spec.
+    pub version_files: Vec<String>,
+    /// Stop looking for version files at the root of a git repository.
+    pub git_root: bool,
+    /// Stop looking for version files at the first directory containing any
+    /// of these, such as `.jvmvj-root`, after checking that directory.
+    pub boundary_markers: Vec<String>,
+    /// Also look for JDKs installed by Homebrew's `openjdk` formulae.
+    pub include_homebrew: bool,
+    /// Distros to prefer, most preferred first, when several JVMs match a
+    /// spec, even over a newer version of another distro.
+    pub distro_priority: Vec<String>,
+    /// The distro to prefer for particular major versions, such as
+    /// `{ 8 = "zulu", 17 = "temurin" }`, ahead of `distro_priority`.
+    pub default_distro: BTreeMap<String, String>,
+    /// Distros that are never selected, such as `oracle` on a machine where
+    /// its licence is a concern. `--include-excluded` overrides this.
+    pub exclude_distros: Vec<String>,
+    /// A shell command run after switching to a different JVM, with
+    /// `JVMVJ_JAVA_HOME` set to its home.
+    pub post_switch: Option<String>,
+    /// Extra architecture names to treat as another, such as
+    /// `{ amd = "x86_64" }`, on top of the built-in `aarch64 = "arm64"` and
+    /// friends.
+    pub arch_aliases: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            version_files: vec![
+                ".java-version".to_string(),
+                ".tool-versions".to_string(),
+                ".sdkmanrc".to_string(),
+            ],
+            git_root: false,
+            boundary_markers: vec![],
+            include_homebrew: false,
+            distro_priority: vec![],
+            default_distro: BTreeMap::new(),
+            exclude_distros: vec![],
+            post_switch: None,
+            arch_aliases: BTreeMap::new(),
+        }
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    Some(home_dir()?.join(".config/jvmvj/config.toml"))
+}
+
+/// The config file named by `explicit_path` (`--config`) or `JVMVJ_CONFIG`.
+fn explicit_config_path(explicit_path: Option<&str>) -> Option<PathBuf> {
+    explicit_path
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("JVMVJ_CONFIG").map(PathBuf::from))
+}
+
+/// The config file `load` reads, whether or not it exists.
+pub fn source_path(explicit_path: Option<&str>) -> Option<PathBuf> {
+    explicit_config_path(explicit_path).or_else(config_path)
+}
+
+/// Loads the config from `explicit_path` (`--config`), `JVMVJ_CONFIG` or the
+/// default location. Only the default may be missing.
+pub fn load(explicit_path: Option<&str>, verbosity: Verbosity) -> Config {
+    let (path, contents) = match explicit_config_path(explicit_path) {
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(contents) => (path, contents),
+            Err(err) => exit_with_err(
+                &format!("Failed to read config file {}: {}", path.display(), err),
+                verbosity,
+            ),
+        },
+        None => {
+            let Some(path) = config_path() else {
+                return Config::default();
+            };
+            let Ok(contents) = fs::read_to_string(&path) else {
+                return Config::default();
+            };
+            (path, contents)
+        }
+    };
+
+    toml::from_str(&contents).unwrap_or_else(|err| {
+        exit_with_err(
+            &format!("Failed to parse config file {}: {}", path.display(), err),
+            verbosity,
+        )
+    })
+}
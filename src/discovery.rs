@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
+use std::process::Command;
+
+use crate::Jvm;
+
+/// Parses the `KEY="value"` lines of a JDK's `release` file.
+fn read_release(home: &Path) -> Option<HashMap<String, String>> {
+    let contents = fs::read_to_string(home.join("release")).ok()?;
+    Some(
+        contents
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| {
+                (
+                    key.trim().to_string(),
+                    value.trim().trim_matches('"').to_string(),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Builds a `Jvm` for a JDK that `java_home` doesn't know about, from the
+/// `release` file in its home directory.
+fn jvm_from_release(
+    home: &Path,
+    bundle_id: String,
+    default_vendor: &str,
+) -> Option<Jvm> {
+    let release = read_release(home)?;
+    let version = release.get("JAVA_VERSION")?.clone();
+    let vendor = release
+        .get("IMPLEMENTOR")
+        .cloned()
+        .unwrap_or_else(|| default_vendor.to_string());
+
+    Some(Jvm {
+        arch: release.get("OS_ARCH").cloned().unwrap_or_default(),
+        bundle_id,
+        enabled: true,
+        home_path: home.to_string_lossy().into_owned(),
+        name: format!("{} {}", vendor, version),
+        platform_version: version.clone(),
+        vendor,
+        version,
+    })
+}
+
+/// The home directory of a JDK given by path. A macOS bundle root such as
+/// `temurin-17.jdk` is resolved to the `Contents/Home` inside it.
+fn bundle_home(path: &Path) -> PathBuf {
+    let inner_home = path.join("Contents/Home");
+    if !path.join("bin/java").exists() && inner_home.join("bin/java").exists() {
+        inner_home
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// A JVM given directly by path, as in asdf's `java path:/some/jdk`.
+pub fn jvm_at_path(path: &Path) -> Jvm {
+    let home = bundle_home(path);
+    jvm_from_release(&home, String::new(), "Java").unwrap_or_else(|| Jvm {
+        enabled: true,
+        home_path: home.to_string_lossy().into_owned(),
+        name: home.display().to_string(),
+        ..Jvm::default()
+    })
+}
+
+/// Where Homebrew is installed: `HOMEBREW_PREFIX`, or its default on Apple
+/// silicon.
+pub fn homebrew_prefix() -> PathBuf {
+    env::var_os("HOMEBREW_PREFIX")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/opt/homebrew"))
+}
+
+/// JDKs installed by the `openjdk` formulae of the Homebrew at `prefix`, which
+/// aren't always symlinked somewhere `java_home` will find them.
+pub fn homebrew_jvms(prefix: &Path) -> Vec<Jvm> {
+    let Ok(formulae) = fs::read_dir(prefix.join("opt")) else {
+        return vec![];
+    };
+
+    formulae
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let formula = entry.file_name().to_string_lossy().into_owned();
+            if !formula.starts_with("openjdk") {
+                return None;
+            }
+            let home = entry.path().join("libexec/openjdk.jdk/Contents/Home");
+            trace!("checking Homebrew formula at {}", home.display());
+            jvm_from_release(&home, format!("homebrew.{}", formula), "Homebrew")
+        })
+        .collect()
+}
+
+/// Where macOS installers put JDKs, each as a `<name>.jdk` bundle.
+pub const JAVA_VIRTUAL_MACHINES: &str = "/Library/Java/JavaVirtualMachines";
+
+/// JDKs found by reading the `release` file of each bundle in `dir`, usually
+/// `JAVA_VIRTUAL_MACHINES`, for when `java_home` is missing or doesn't list
+/// them all. The bundle ID comes from the bundle's `Info.plist`, falling back
+/// to its directory name.
+pub fn scanned_jvms(dir: &Path) -> Vec<Jvm> {
+    let Ok(bundles) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    bundles
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let bundle = entry.path();
+            let home = bundle.join("Contents/Home");
+            trace!("scanning {}", home.display());
+            let bundle_id =
+                plist::Value::from_file(bundle.join("Contents/Info.plist"))
+                    .ok()
+                    .and_then(|info| {
+                        info.as_dictionary()?
+                            .get("CFBundleIdentifier")?
+                            .as_string()
+                            .map(str::to_string)
+                    })
+                    .unwrap_or_else(|| {
+                        entry.file_name().to_string_lossy().into_owned()
+                    });
+            jvm_from_release(&home, bundle_id, "Java")
+        })
+        .collect()
+}
+
+/// JDKs registered by their installers under `HKLM\SOFTWARE\JavaSoft\JDK`,
+/// plus any unregistered ones in the usual install directories.
+///
+/// Windows only records each JDK's home, so the rest comes from its `release`
+/// file: `version` and `platform_version` from `JAVA_VERSION`, `vendor` from
+/// `IMPLEMENTOR` and `arch` from `OS_ARCH`. `bundle_id` is
+/// `windows.<directory name>` and every JDK is enabled. Directories without a
+/// `release` file aren't JDKs and are skipped.
+#[cfg(windows)]
+pub fn windows_jvms() -> Vec<Jvm> {
+    let program_files = env::var_os("ProgramFiles")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\Program Files"));
+    let mut homes = registry_homes();
+    for vendor_dir in [
+        "Java",
+        "Eclipse Adoptium",
+        "Zulu",
+        "Microsoft",
+        "Amazon Corretto",
+    ] {
+        if let Ok(entries) = fs::read_dir(program_files.join(vendor_dir)) {
+            homes.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path()),
+            );
+        }
+    }
+
+    let mut jvms = vec![];
+    let found = homes
+        .iter()
+        .filter_map(|home| {
+            let dir_name = home.file_name()?.to_string_lossy().into_owned();
+            trace!("checking {}", home.display());
+            jvm_from_release(home, format!("windows.{}", dir_name), "Java")
+        })
+        .collect();
+    merge(&mut jvms, found);
+    jvms
+}
+
+/// The `JavaHome` values under `HKLM\SOFTWARE\JavaSoft\JDK`, read with
+/// `reg query` rather than a registry binding.
+#[cfg(windows)]
+fn registry_homes() -> Vec<PathBuf> {
+    let Ok(output) = Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\JavaSoft\JDK",
+            "/s",
+            "/v",
+            "JavaHome",
+        ])
+        .output()
+    else {
+        return vec![];
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (_, home) =
+                line.trim().strip_prefix("JavaHome")?.split_once("REG_SZ")?;
+            Some(PathBuf::from(home.trim()))
+        })
+        .collect()
+}
+
+fn same_home(a: &Jvm, b: &Jvm) -> bool {
+    let canonical = |jvm: &Jvm| {
+        Path::new(&jvm.home_path)
+            .canonicalize()
+            .unwrap_or_else(|_| PathBuf::from(&jvm.home_path))
+    };
+    a.home_path == b.home_path || canonical(a) == canonical(b)
+}
+
+/// Adds the JVMs from `extra` whose home isn't already in `jvms`.
+pub fn merge(jvms: &mut Vec<Jvm>, extra: Vec<Jvm>) {
+    for jvm in extra {
+        if !jvms.iter().any(|known| same_home(known, &jvm)) {
+            jvms.push(jvm);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! The `--where` expressions that `list` filters by, over a few of each JVM's
+//! fields:
+//!
+//! ```text
+//! expression = all { "or" all }
+//! all        = comparison { "and" comparison }
+//! comparison = field operator value
+//! field      = "major" | "arch" | "vendor" | "enabled"
+//! operator   = "==" | "!=" | ">=" | "<=" | ">" | "<"
+//! ```
+//!
+//! `and` binds tighter than `or`, and there are no parentheses. `major` is
+//! compared as a number. The other fields only support `==` and `!=`, and
+//! compare case-insensitively, with `arch` aliases such as `aarch64` allowed.
+//! A value with spaces in it can be quoted, as in `vendor=="Azul Systems, Inc."`.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::{normalize_arch, Jvm};
+
+#[derive(Clone, Copy)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    AtLeast,
+    AtMost,
+    Greater,
+    Less,
+}
+
+impl Operator {
+    pub fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            "==" => Operator::Equal,
+            "!=" => Operator::NotEqual,
+            ">=" => Operator::AtLeast,
+            "<=" => Operator::AtMost,
+            ">" => Operator::Greater,
+            "<" => Operator::Less,
+            _ => return None,
+        })
+    }
+
+    /// Whether a value comparing to the operand as `ordering` satisfies it.
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Equal => ordering == Ordering::Equal,
+            Operator::NotEqual => ordering != Ordering::Equal,
+            Operator::AtLeast => ordering != Ordering::Less,
+            Operator::AtMost => ordering != Ordering::Greater,
+            Operator::Greater => ordering == Ordering::Greater,
+            Operator::Less => ordering == Ordering::Less,
+        }
+    }
+}
+
+enum Comparison {
+    Major(Operator, u16),
+    Arch(Operator, String),
+    Vendor(Operator, String),
+    Enabled(Operator, bool),
+}
+
+impl Comparison {
+    fn parse(
+        field: &str,
+        operator: &str,
+        value: &str,
+        arch_aliases: &BTreeMap<String, String>,
+    ) -> Result<Self, String> {
+        let operator = Operator::parse(operator)
+            .ok_or_else(|| format!("unknown operator {}", operator))?;
+        let comparison = match field {
+            "major" => Comparison::Major(
+                operator,
+                value
+                    .parse()
+                    .map_err(|_| format!("major {} should be a number", value))?,
+            ),
+            "arch" => {
+                Comparison::Arch(operator, normalize_arch(value, arch_aliases))
+            }
+            "vendor" => Comparison::Vendor(operator, value.to_lowercase()),
+            "enabled" => Comparison::Enabled(
+                operator,
+                value.parse().map_err(|_| {
+                    format!("enabled {} should be true or false", value)
+                })?,
+            ),
+            _ => {
+                return Err(format!(
+                "unknown field {}; valid fields are major, arch, vendor, enabled",
+                field
+            ))
+            }
+        };
+        match (&comparison, operator) {
+            (Comparison::Major(..), _)
+            | (_, Operator::Equal)
+            | (_, Operator::NotEqual) => Ok(comparison),
+            _ => Err(format!("{} can only be compared with == or !=", field)),
+        }
+    }
+
+    fn matches(&self, jvm: &Jvm) -> bool {
+        match self {
+            Comparison::Major(operator, major) => {
+                operator.holds(jvm.major_version().cmp(major))
+            }
+            Comparison::Arch(operator, arch) => operator.holds(jvm.arch.cmp(arch)),
+            Comparison::Vendor(operator, vendor) => {
+                operator.holds(jvm.vendor.to_lowercase().cmp(vendor))
+            }
+            Comparison::Enabled(operator, enabled) => {
+                operator.holds(jvm.enabled.cmp(enabled))
+            }
+        }
+    }
+}
+
+/// A parsed `--where` expression: groups of comparisons that must all hold,
+/// of which any one group must.
+pub struct Filter {
+    any_of: Vec<Vec<Comparison>>,
+}
+
+impl Filter {
+    pub fn parse(
+        expression: &str,
+        arch_aliases: &BTreeMap<String, String>,
+    ) -> Result<Self, String> {
+        let tokens = tokenize(expression)?;
+        let mut tokens = tokens.iter().map(String::as_str);
+        let mut any_of = vec![vec![]];
+        loop {
+            let field = tokens.next().ok_or("expected a field")?;
+            let operator = tokens
+                .next()
+                .ok_or_else(|| format!("expected an operator after {}", field))?;
+            let value = tokens
+                .next()
+                .ok_or_else(|| format!("expected a value after {}", operator))?;
+            let comparison =
+                Comparison::parse(field, operator, value, arch_aliases)?;
+            any_of.last_mut().unwrap().push(comparison);
+            match tokens.next() {
+                None => break,
+                Some("and") => {}
+                Some("or") => any_of.push(vec![]),
+                Some(token) => {
+                    return Err(format!("expected and or or, found {}", token))
+                }
+            }
+        }
+        Ok(Filter { any_of })
+    }
+
+    pub fn matches(&self, jvm: &Jvm) -> bool {
+        self.any_of
+            .iter()
+            .any(|all| all.iter().all(|comparison| comparison.matches(jvm)))
+    }
+}
+
+/// Splits an expression into words, quoted values and operators, which need
+/// no spaces around them.
+fn tokenize(expression: &str) -> Result<Vec<String>, String> {
+    let is_operator = |c: char| "=!<>".contains(c);
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some(next) if next == c => break,
+                    Some(next) => value.push(next),
+                    None => return Err(format!("unterminated {}", c)),
+                }
+            }
+            tokens.push(value);
+        } else {
+            let in_token = |next: &char| {
+                !next.is_whitespace() && is_operator(*next) == is_operator(c)
+            };
+            let mut token = String::new();
+            while let Some(next) = chars.next_if(in_token) {
+                token.push(next);
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
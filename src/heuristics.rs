@@ -0,0 +1,80 @@
+//! Best-effort resolvers that guess the required Java version from build
+//! files when a project has no explicit version file. These scan the text of
+//! the files rather than evaluating them, so they only recognise literal
+//! versions written in the common forms.
+
+use std::fs;
+use std::path::Path;
+
+/// Finds a Gradle toolchain declaration such as
+/// `languageVersion = JavaLanguageVersion.of(17)` in `build.gradle.kts` or
+/// `build.gradle`.
+pub fn gradle_version(dir: &Path) -> Option<String> {
+    ["build.gradle.kts", "build.gradle"]
+        .iter()
+        .find_map(|file_name| {
+            let contents = fs::read_to_string(dir.join(file_name)).ok()?;
+            let (_, rest) = contents.split_once("JavaLanguageVersion.of(")?;
+            let (version, _) = rest.split_once(')')?;
+            let version = version.trim().trim_matches('"');
+            version.parse::<u16>().ok()?;
+            Some(version.to_string())
+        })
+}
+
+/// Finds the `<maven.compiler.release>` or, failing that, `<java.version>`
+/// property in a `pom.xml`. The legacy `1.8` form is read as `8`; anything
+/// that isn't a literal version, such as `${jdk}`, is ignored.
+pub fn maven_version(dir: &Path) -> Option<String> {
+    let contents = fs::read_to_string(dir.join("pom.xml")).ok()?;
+    ["maven.compiler.release", "java.version"]
+        .iter()
+        .find_map(|property| {
+            let (_, rest) = contents.split_once(&format!("<{}>", property))?;
+            let (version, _) = rest.split_once(&format!("</{}>", property))?;
+            let version = version.trim();
+            let version = version.strip_prefix("1.").unwrap_or(version);
+            version.parse::<u16>().ok()?;
+            Some(version.to_string())
+        })
+}
+
+/// Docker images that ship a JDK, and the distro each implies. Tags of other
+/// images, such as `maven:3.9`, aren't Java versions.
+const JDK_IMAGES: [(&str, Option<&str>); 8] = [
+    ("eclipse-temurin", Some("temurin")),
+    ("amazoncorretto", Some("corretto")),
+    ("zulu-openjdk", Some("zulu")),
+    ("ibm-semeru-runtimes", Some("semeru")),
+    ("sapmachine", Some("sapmachine")),
+    ("liberica-openjdk-debian", Some("liberica")),
+    ("liberica-openjdk-alpine", Some("liberica")),
+    ("openjdk", None),
+];
+
+/// Finds the major version in the tag of a `Dockerfile`'s first `FROM` line,
+/// such as `FROM eclipse-temurin:17-jdk`, with the image's distro as a hint.
+pub fn dockerfile_version(dir: &Path) -> Option<String> {
+    let contents = fs::read_to_string(dir.join("Dockerfile")).ok()?;
+    let from = contents.lines().find_map(|line| {
+        let (instruction, rest) = line.trim().split_once(char::is_whitespace)?;
+        instruction.eq_ignore_ascii_case("FROM").then_some(rest)
+    })?;
+    let image = from
+        .split_whitespace()
+        .find(|word| !word.starts_with("--"))?;
+
+    let (name, tag) = image.rsplit_once(':')?;
+    let name = name.rsplit('/').next()?;
+    let (_, distro) = JDK_IMAGES.iter().find(|(image, _)| *image == name)?;
+    let major = tag
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>();
+    major.parse::<u16>().ok()?;
+
+    Some(match distro {
+        Some(distro) => format!("{}-{}", distro, major),
+        None => major,
+    })
+}
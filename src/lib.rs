@@ -0,0 +1,3809 @@
+/// Prints a debugging trace to stderr when run with `-vv`.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::TRACE.load(std::sync::atomic::Ordering::Relaxed) {
+            eprintln!("jvmvj: {}", format_args!($($arg)*));
+        }
+    };
+}
+
+mod cache;
+mod config;
+mod discovery;
+mod filter;
+mod heuristics;
+mod rc;
+
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, IsTerminal, Read};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::process::exit;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::OnceLock;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tabled::{builder::Builder, settings::Style, Table};
+
+#[allow(dead_code)]
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Jvm {
+    #[serde(rename(deserialize = "JVMArch"))]
+    arch: String,
+    #[serde(rename(deserialize = "JVMBundleID"))]
+    bundle_id: String,
+    #[serde(rename(deserialize = "JVMEnabled"))]
+    enabled: bool,
+    #[serde(rename(deserialize = "JVMHomePath"))]
+    home_path: String,
+    #[serde(rename(deserialize = "JVMName"))]
+    name: String,
+    #[serde(rename(deserialize = "JVMPlatformVersion"))]
+    platform_version: String,
+    #[serde(rename(deserialize = "JVMVendor"))]
+    vendor: String,
+    #[serde(rename(deserialize = "JVMVersion"))]
+    version: String,
+}
+
+fn parse_major_version(version: &str) -> Result<u16, &'static str> {
+    let (major_version, rest) = version
+        .split_once('.')
+        .ok_or("should contain at least one period")?;
+
+    let major_version = match major_version {
+        "1" => {
+            rest.split_once('.')
+                .ok_or("should contain at least two periods when 1-prefixed")?
+                .0
+        }
+        otherwise => otherwise,
+    };
+
+    major_version
+        .parse::<u16>()
+        .map_err(|_| "should have a numeric major version")
+}
+
+/// The numbers of a version such as `17.0.9+11`, with any that are left off
+/// as 0. In a legacy version such as `1.8.0_302`, the update follows the `_`.
+#[derive(Serialize)]
+struct VersionComponents {
+    feature: u16,
+    interim: u16,
+    update: u16,
+    patch: u16,
+}
+
+fn parse_version_components(version: &str) -> Option<VersionComponents> {
+    let version = version.split(['+', '-']).next()?;
+    if let Some(legacy) = version.strip_prefix("1.") {
+        let (numbers, update) = legacy.split_once('_').unwrap_or((legacy, "0"));
+        let mut numbers = numbers.split('.');
+        return Some(VersionComponents {
+            feature: numbers.next()?.parse().ok()?,
+            interim: numbers.next().unwrap_or("0").parse().ok()?,
+            update: update.parse().ok()?,
+            patch: 0,
+        });
+    }
+    let mut numbers = version.split('.').map(str::parse::<u16>);
+    let mut next = || numbers.next().unwrap_or(Ok(0)).ok();
+    Some(VersionComponents {
+        feature: next()?,
+        interim: next()?,
+        update: next()?,
+        patch: next()?,
+    })
+}
+
+impl Jvm {
+    /// The major version from `version`, falling back to `platform_version`
+    /// for distros that leave the former empty or malformed.
+    fn major_version(&self) -> u16 {
+        parse_major_version(&self.version).unwrap_or_else(|reason| {
+            parse_major_version(&self.platform_version).unwrap_or_else(|_| {
+                exit_with_err(
+                    &format!(
+                        "Version number {} of jvm {} {}!",
+                        self.version, self.home_path, reason
+                    ),
+                    Verbosity::Normal,
+                )
+            })
+        })
+    }
+
+    /// The components of `version`, or of `platform_version` if `version`
+    /// doesn't parse.
+    fn version_components(&self) -> Option<VersionComponents> {
+        parse_version_components(&self.version)
+            .or_else(|| parse_version_components(&self.platform_version))
+    }
+
+    /// The table cell for one of `LIST_FIELDS`, shortened as `--short-arch`
+    /// and `--trim-bundle` ask.
+    fn field(&self, field: &str, options: &ListOptions) -> String {
+        match field {
+            "version" => self.version.clone(),
+            "name" => self.name.clone(),
+            "arch" if options.short_arch => short_arch_label(&self.arch).to_string(),
+            "arch" => self.arch.clone(),
+            "vendor" => self.vendor.clone(),
+            "home" => self.home_path.clone(),
+            "bundle_id" if options.trim_bundle => {
+                trim_bundle_id(&self.bundle_id).to_string()
+            }
+            "bundle_id" => self.bundle_id.clone(),
+            "platform_version" => self.platform_version.clone(),
+            "enabled" => self.enabled.to_string(),
+            _ => unreachable!("unknown field {}", field),
+        }
+    }
+}
+
+/// Names distros use for the same architecture, mapped to the one
+/// `java_home` reports. `arch_aliases` in the config adds to these.
+const ARCH_ALIASES: [(&str, &str); 4] = [
+    ("aarch64", "arm64"),
+    ("arm", "arm64"),
+    ("amd64", "x86_64"),
+    ("x64", "x86_64"),
+];
+
+fn normalize_arch(arch: &str, aliases: &BTreeMap<String, String>) -> String {
+    let arch = arch.to_lowercase();
+    aliases
+        .get(&arch)
+        .map(String::as_str)
+        .or_else(|| {
+            ARCH_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == arch)
+                .map(|(_, normal)| *normal)
+        })
+        .map(str::to_string)
+        .unwrap_or(arch)
+}
+
+fn short_arch_label(arch: &str) -> &str {
+    match arch {
+        "x86_64" => "x64",
+        "arm64" => "arm",
+        otherwise => otherwise,
+    }
+}
+
+/// The columns `--fields` can choose from.
+const LIST_FIELDS: [&str; 8] = [
+    "version",
+    "name",
+    "arch",
+    "vendor",
+    "home",
+    "bundle_id",
+    "platform_version",
+    "enabled",
+];
+
+const TABLE_STYLES: [&str; 6] =
+    ["rounded", "sharp", "ascii", "markdown", "psql", "blank"];
+
+struct ListOptions<'a> {
+    style: &'a str,
+    home_only: bool,
+    print0: bool,
+    short_arch: bool,
+    vendor: Option<&'a str>,
+    arch: Option<String>,
+    json: bool,
+    jsonl: bool,
+    count: bool,
+    /// Which JVMs to list by their `enabled` flag: enabled ones by default
+    /// or with `--enabled`, disabled ones with `--disabled`, and all with
+    /// `--include-disabled`.
+    enabled: Option<bool>,
+    group_by: Option<&'a str>,
+    sort: &'a str,
+    installed_only: bool,
+    porcelain: bool,
+    limit: Option<usize>,
+    only_major: Option<u16>,
+    max_width: Option<usize>,
+    /// Whether to color the table, from `--color`. `auto` colors only when
+    /// stdout is a terminal and `NO_COLOR` is unset.
+    color: bool,
+    trim_bundle: bool,
+    /// The table's columns, in order, from `--fields`.
+    fields: Vec<&'a str>,
+    filter: Option<filter::Filter>,
+    compat: Vec<Constraint>,
+}
+
+/// One comparison of a `--compat` constraint such as `>=17 <21`, against
+/// only the major version when it's a bare major and the full version
+/// otherwise.
+struct Constraint {
+    operator: filter::Operator,
+    version: String,
+}
+
+impl Constraint {
+    fn parse_all(constraints: &str) -> Vec<Self> {
+        constraints
+            .split_whitespace()
+            .map(|constraint| {
+                let split = constraint
+                    .find(|c: char| !"=!<>".contains(c))
+                    .unwrap_or(constraint.len());
+                let (operator, version) = constraint.split_at(split);
+                let operator = match operator {
+                    "" => Some(filter::Operator::Equal),
+                    operator => filter::Operator::parse(operator),
+                };
+                match operator {
+                    Some(operator) if !version.is_empty() => Constraint {
+                        operator,
+                        version: version.to_string(),
+                    },
+                    _ => exit_with_err(
+                        &format!(
+                            "--compat {} should be a version after one of ==, !=, >=, <=, >, <",
+                            constraint
+                        ),
+                        Verbosity::Normal,
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    fn allows(&self, jvm: &Jvm) -> bool {
+        let ordering = match self.version.parse::<u16>() {
+            Ok(major) => jvm.major_version().cmp(&major),
+            Err(_) => compare_versions(&jvm.version, &self.version),
+        };
+        self.operator.holds(ordering)
+    }
+}
+
+/// The `enabled` value `list` keeps, from whichever one of `--enabled`,
+/// `--disabled` and `--include-disabled` is given.
+fn enabled_filter(args: &[String]) -> Option<bool> {
+    let given = [
+        ("--enabled", Some(true)),
+        ("--disabled", Some(false)),
+        ("--include-disabled", None),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| has_flag(args, flag))
+    .collect::<Vec<(&str, Option<bool>)>>();
+    match given[..] {
+        [] => Some(true),
+        [(_, enabled)] => enabled,
+        _ => exit_with_err(
+            &format!(
+                "{} contradict each other; give only one",
+                given
+                    .iter()
+                    .map(|(flag, _)| *flag)
+                    .collect::<Vec<&str>>()
+                    .join(" and ")
+            ),
+            Verbosity::Normal,
+        ),
+    }
+}
+
+impl<'a> ListOptions<'a> {
+    fn from_args(args: &'a [String], config: &config::Config) -> Self {
+        let limit =
+            flag_value(args, "--limit").map(|limit| match limit.parse::<usize>() {
+                Ok(limit) if limit > 0 => limit,
+                _ => exit_with_err(
+                    &format!("--limit {} should be a positive number!", limit),
+                    Verbosity::Normal,
+                ),
+            });
+
+        let only_major = flag_value(args, "--only-major").map(|major| {
+            major.parse::<u16>().unwrap_or_else(|_| {
+                exit_with_err(
+                    &format!(
+                        "--only-major {} should be a major version number!",
+                        major
+                    ),
+                    Verbosity::Normal,
+                )
+            })
+        });
+
+        let max_width = flag_value(args, "--max-width").map(|width| {
+            width.parse::<usize>().unwrap_or_else(|_| {
+                exit_with_err(
+                    &format!("--max-width {} should be a number!", width),
+                    Verbosity::Normal,
+                )
+            })
+        });
+
+        let color = match flag_value(args, "--color").unwrap_or("auto") {
+            "always" => true,
+            "never" => false,
+            "auto" => {
+                io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+            color => exit_with_err(
+                &format!(
+                    "Unknown --color {}! Valid options are: auto, always, never",
+                    color
+                ),
+                Verbosity::Normal,
+            ),
+        };
+
+        let fields = flag_value(args, "--fields")
+            .unwrap_or("version,name,arch")
+            .split(',')
+            .map(|field| {
+                LIST_FIELDS
+                    .iter()
+                    .copied()
+                    .find(|known| *known == field)
+                    .unwrap_or_else(|| {
+                        exit_with_err(
+                            &format!(
+                                "Unknown field {}! Valid fields are: {}",
+                                field,
+                                LIST_FIELDS.join(", ")
+                            ),
+                            Verbosity::Normal,
+                        )
+                    })
+            })
+            .collect();
+
+        let filter = flag_value(args, "--where").map(|expression| {
+            filter::Filter::parse(expression, &config.arch_aliases).unwrap_or_else(
+                |err| {
+                    exit_with_err(
+                        &format!("Invalid --where {}: {}", expression, err),
+                        Verbosity::Normal,
+                    )
+                },
+            )
+        });
+
+        ListOptions {
+            style: flag_value(args, "--style").unwrap_or("rounded"),
+            home_only: has_flag(args, "--home"),
+            print0: has_flag(args, "--print0"),
+            short_arch: has_flag(args, "--short-arch"),
+            vendor: flag_value(args, "--vendor"),
+            arch: flag_value(args, "--arch")
+                .filter(|arch| *arch != "all")
+                .map(|arch| normalize_arch(arch, &config.arch_aliases)),
+            json: has_flag(args, "--json"),
+            jsonl: has_flag(args, "--jsonl"),
+            count: has_flag(args, "--count"),
+            enabled: enabled_filter(args),
+            group_by: flag_value(args, "--group-by"),
+            sort: flag_value(args, "--sort").unwrap_or("version"),
+            installed_only: has_flag(args, "--installed-only"),
+            porcelain: has_flag(args, "--porcelain"),
+            limit,
+            only_major,
+            max_width,
+            color,
+            trim_bundle: has_flag(args, "--trim-bundle"),
+            fields,
+            filter,
+            compat: flag_value(args, "--compat")
+                .map(Constraint::parse_all)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn includes(&self, jvm: &Jvm) -> bool {
+        let vendor_matches = self.vendor.is_none_or(|vendor| {
+            jvm.vendor.to_lowercase().contains(&vendor.to_lowercase())
+        });
+        let arch_matches = self.arch.as_ref().is_none_or(|arch| &jvm.arch == arch);
+        let enabled_matches =
+            self.enabled.is_none_or(|enabled| jvm.enabled == enabled);
+        let installed_matches = !self.installed_only || is_installed(jvm);
+        let major_matches = self
+            .only_major
+            .is_none_or(|major| jvm.major_version() == major);
+        let filter_matches = self
+            .filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(jvm));
+        let compat_matches =
+            self.compat.iter().all(|constraint| constraint.allows(jvm));
+        vendor_matches
+            && arch_matches
+            && enabled_matches
+            && installed_matches
+            && major_matches
+            && filter_matches
+            && compat_matches
+    }
+}
+
+/// When the JVM's home directory was last modified, which is usually when it
+/// was installed.
+fn installed_at(jvm: &Jvm) -> Option<SystemTime> {
+    fs::metadata(&jvm.home_path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// The JVMs to list, newest version first, or newest install first with
+/// `--sort installed`. JVMs whose home is missing sort last by install.
+/// `--limit` keeps only the first few.
+fn listed<'a>(jvms: &'a [Jvm], options: &ListOptions) -> Vec<&'a Jvm> {
+    let mut jvms = jvms
+        .iter()
+        .filter(|jvm| options.includes(jvm))
+        .collect::<Vec<&Jvm>>();
+    match options.sort {
+        "version" => jvms.sort_by(|a, b| compare_versions(&b.version, &a.version)),
+        "installed" => jvms.sort_by_key(|jvm| Reverse(installed_at(jvm))),
+        sort => exit_with_err(
+            &format!(
+                "Cannot sort by {}! Valid options are: version, installed",
+                sort
+            ),
+            Verbosity::Normal,
+        ),
+    }
+    if let Some(limit) = options.limit {
+        jvms.truncate(limit);
+    }
+    jvms
+}
+
+/// The first line of `--porcelain` output. The rows after it keep their
+/// format for as long as the header is unchanged, so scripts can rely on it.
+const PORCELAIN_HEADER: &str = "# jvmvj porcelain v1";
+
+/// A `--porcelain` row: `V`, then the version, name, arch and home, separated
+/// by tabs.
+fn porcelain_row(jvm: &Jvm) -> String {
+    format!(
+        "V\t{}\t{}\t{}\t{}",
+        jvm.version, jvm.name, jvm.arch, jvm.home_path
+    )
+}
+
+/// The vendor segment of a reverse-DNS bundle ID, such as `adoptium` for
+/// `net.adoptium.17.jdk`. IDs with fewer than three segments, like Homebrew's
+/// `homebrew.openjdk@17`, are already short and kept as they are.
+fn trim_bundle_id(bundle_id: &str) -> &str {
+    let segments = bundle_id.split('.').collect::<Vec<&str>>();
+    if segments.len() < 3 {
+        bundle_id
+    } else {
+        segments[1]
+    }
+}
+
+fn json_jvm(jvm: &Jvm, options: &ListOptions) -> serde_json::Value {
+    let mut value = serde_json::to_value(jvm).unwrap();
+    value["components"] = json!(jvm.version_components());
+    if options.trim_bundle {
+        value["bundle_id"] = json!(trim_bundle_id(&jvm.bundle_id));
+    }
+    value
+}
+
+fn list_all(jvms: &[Jvm], options: &ListOptions) {
+    let jvms = listed(jvms, options);
+
+    if options.count {
+        println!("{}", jvms.len());
+        return;
+    }
+
+    if options.json {
+        let jvms = jvms
+            .iter()
+            .map(|jvm| json_jvm(jvm, options))
+            .collect::<Vec<serde_json::Value>>();
+        println!("{}", serde_json::to_string_pretty(&jvms).unwrap());
+        return;
+    }
+
+    if options.jsonl {
+        for jvm in jvms {
+            println!("{}", json_jvm(jvm, options));
+        }
+        return;
+    }
+
+    if options.porcelain {
+        println!("{}", PORCELAIN_HEADER);
+        for jvm in jvms {
+            println!("{}", porcelain_row(jvm));
+        }
+        return;
+    }
+
+    if options.home_only || options.print0 {
+        let terminator = if options.print0 { '\0' } else { '\n' };
+        for jvm in jvms {
+            print!("{}{}", jvm.home_path, terminator);
+        }
+        return;
+    }
+
+    match options.group_by {
+        None => println!("{}", render_table(&jvms, options)),
+        Some("vendor") => {
+            let mut groups = BTreeMap::<&str, Vec<&Jvm>>::new();
+            for jvm in jvms {
+                groups.entry(&jvm.vendor).or_default().push(jvm);
+            }
+            for (vendor, group) in groups {
+                println!("{}", vendor);
+                println!("{}", render_table(&group, options));
+            }
+        }
+        Some(field) => exit_with_err(
+            &format!("Cannot group by {}! Valid options are: vendor", field),
+            Verbosity::Normal,
+        ),
+    }
+}
+
+fn render_table(jvms: &[&Jvm], options: &ListOptions) -> String {
+    let mut rows = jvms
+        .iter()
+        .map(|jvm| {
+            options
+                .fields
+                .iter()
+                .map(|field| jvm.field(field, options))
+                .collect()
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    // Only the name is cut to fit, as the other columns are no use truncated.
+    let name_column = options.fields.iter().position(|field| *field == "name");
+    if let (Some(max_width), Some(column)) = (options.max_width, name_column) {
+        let width =
+            styled_table(&options.fields, &rows, options.style).total_width();
+        if width > max_width {
+            let name_width = rows
+                .iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0);
+            let budget = name_width.saturating_sub(width - max_width).max(1);
+            for row in &mut rows {
+                row[column] = truncate_with_ellipsis(&row[column], budget);
+            }
+        }
+    }
+
+    let table = styled_table(&options.fields, &rows, options.style).to_string();
+    if options.color {
+        colorize(&table, jvms)
+    } else {
+        table
+    }
+}
+
+/// ANSI codes for a row: green for the active JVM, dim for a disabled one
+/// and bold for the newest version, combined when several apply.
+fn row_color(jvm: &Jvm, java_home: Option<&str>, newest: Option<&str>) -> String {
+    let mut codes = vec![];
+    if newest == Some(jvm.version.as_str()) {
+        codes.push("1");
+    }
+    if !jvm.enabled {
+        codes.push("2");
+    }
+    if java_home == Some(jvm.home_path.as_str()) {
+        codes.push("32");
+    }
+    codes.join(";")
+}
+
+/// Colors each row of a rendered table. Border lines have no letters or
+/// digits, so the first line that does is the header and each after it is
+/// the next JVM's row.
+fn colorize(table: &str, jvms: &[&Jvm]) -> String {
+    let java_home = env::var("JAVA_HOME").ok();
+    let newest = jvms
+        .iter()
+        .map(|jvm| jvm.version.as_str())
+        .max_by(|a, b| compare_versions(a, b));
+    let mut rows = jvms.iter();
+    let mut seen_header = false;
+    table
+        .lines()
+        .map(|line| {
+            if !line.chars().any(char::is_alphanumeric) {
+                return line.to_string();
+            }
+            if !seen_header {
+                seen_header = true;
+                return line.to_string();
+            }
+            match rows.next() {
+                Some(jvm) => {
+                    let codes = row_color(jvm, java_home.as_deref(), newest);
+                    if codes.is_empty() {
+                        line.to_string()
+                    } else {
+                        format!("\x1b[{}m{}\x1b[0m", codes, line)
+                    }
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// `text` cut to `width` characters, ending with an ellipsis if it was cut.
+fn truncate_with_ellipsis(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut truncated = text.chars().take(width - 1).collect::<String>();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn styled_table(header: &[&str], rows: &[Vec<String>], style: &str) -> Table {
+    let mut builder = Builder::default();
+    builder.push_record(header.iter().copied());
+    for row in rows {
+        builder.push_record(row.iter().map(String::as_str));
+    }
+    let mut table = builder.build();
+    match style {
+        "rounded" => table.with(Style::rounded()),
+        "sharp" => table.with(Style::sharp()),
+        "ascii" => table.with(Style::ascii()),
+        "markdown" => table.with(Style::markdown()),
+        "psql" => table.with(Style::psql()),
+        "blank" => table.with(Style::blank()),
+        _ => exit_with_err(
+            &format!(
+                "Unknown table style {}! Valid styles are: {}",
+                style,
+                TABLE_STYLES.join(", ")
+            ),
+            Verbosity::Normal,
+        ),
+    };
+    table
+}
+
+#[derive(Debug)]
+struct V {
+    /// The major version, or `None` for a distro-only spec such as `temurin`,
+    /// which matches that distro's newest JVM.
+    number: Option<u16>,
+    distro: Option<String>,
+    /// `-ea`, as in `21-ea`: match only early-access builds.
+    early_access: bool,
+    /// `@arch`, as in `17@aarch64`: match only that architecture.
+    arch: Option<String>,
+}
+
+/// Prefixes that tools write in front of a version without naming a distro,
+/// as in `java-17` or `openjdk-17`.
+const GENERIC_DISTROS: [&str; 3] = ["java", "openjdk", "jdk"];
+
+/// The distro a spec starts with, after any dashes from a spec passed
+/// literally after `--`, as in `-zulu-17`.
+fn get_distro(spec: &str) -> Option<String> {
+    let dspec: String = spec
+        .trim_start_matches('-')
+        .chars()
+        .take_while(|c| c.is_alphabetic())
+        .collect();
+    if dspec.is_empty() || GENERIC_DISTROS.contains(&dspec.to_lowercase().as_str()) {
+        None
+    } else {
+        Some(dspec)
+    }
+}
+
+/// Drops a generic prefix with a 64-bit marker, as in Homebrew's
+/// `openjdk64-17.0.9`, which names no distro.
+fn strip_generic_64(spec: &str) -> &str {
+    GENERIC_DISTROS
+        .iter()
+        .map(|generic| format!("{}64-", generic))
+        .find(|prefix| {
+            spec.get(..prefix.len())
+                .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+        })
+        .map_or(spec, |prefix| &spec[prefix.len()..])
+}
+
+/// Splits a distro written after the version, as SDKMAN does in
+/// `17.0.9-librca`, translating SDKMAN's vendor codes.
+fn split_distro_suffix(spec: &str) -> Option<(&str, Option<String>)> {
+    let (version, suffix) = spec.rsplit_once('-')?;
+    if !version.starts_with(|c: char| c.is_ascii_digit())
+        || suffix.is_empty()
+        || !suffix.chars().all(char::is_alphabetic)
+    {
+        return None;
+    }
+    let distro = SDKMAN_VENDORS
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(suffix))
+        .map(|(_, distro)| distro.to_string())
+        .or_else(|| get_distro(suffix));
+    Some((version, distro))
+}
+
+fn get_version_from_input(spec: &str) -> Option<V> {
+    if let Some((version, arch)) = spec.rsplit_once('@') {
+        return Some(V {
+            arch: Some(arch.to_string()).filter(|arch| !arch.is_empty()),
+            ..get_version_from_input(version)?
+        });
+    }
+    let spec = strip_generic_64(spec);
+    // A glob such as `temurin*17` is the distro, up to any trailing version.
+    if spec.contains('*') {
+        let version_start = spec
+            .rfind(|c: char| !c.is_ascii_digit() && c != '.')
+            .map_or(0, |i| i + 1);
+        let (glob, version) = spec.split_at(version_start);
+        let number = match version {
+            "" => None,
+            version => get_version_from_input(version)?.number,
+        };
+        return Some(V {
+            distro: Some(glob.trim_end_matches('-').to_string()),
+            number,
+            early_access: false,
+            arch: None,
+        });
+    }
+    if let Some(version) = spec.strip_suffix("-ea") {
+        return Some(V {
+            early_access: true,
+            ..get_version_from_input(version)?
+        });
+    }
+    if let Some((version, distro)) = split_distro_suffix(spec) {
+        let number = get_version_from_input(version)?.number;
+        return Some(V {
+            distro,
+            number,
+            early_access: false,
+            arch: None,
+        });
+    }
+
+    let distro = get_distro(spec);
+    let version = spec
+        .chars()
+        .skip_while(|c| c.is_alphabetic() || *c == '-')
+        .collect::<String>();
+    if version.is_empty() {
+        return distro.map(|distro| V {
+            distro: Some(distro),
+            number: None,
+            early_access: false,
+            arch: None,
+        });
+    }
+    let number = match version.split_once('.') {
+        Some(("1", ver)) => ver.parse::<u16>().ok(),
+        Some((ver, _)) => ver.parse::<u16>().ok(),
+        _ => version.parse::<u16>().ok(),
+    };
+    number.map(|n| V {
+        distro,
+        number: Some(n),
+        early_access: false,
+        arch: None,
+    })
+}
+
+/// Groups of names for the same distro, which report themselves
+/// inconsistently across versions. A spec naming any member of a group
+/// matches a JVM identified by any other member.
+const DISTRO_ALIASES: [&[&str]; 1] =
+    [&["graalvm", "graal", "org.graalvm", "com.oracle.graalvm"]];
+
+/// SDKMAN's vendor codes, as in `17.0.9-tem`, and the distro each names.
+const SDKMAN_VENDORS: [(&str, &str); 12] = [
+    ("tem", "temurin"),
+    ("zulu", "zulu"),
+    ("amzn", "corretto"),
+    ("librca", "liberica"),
+    ("nik", "liberica"),
+    ("graal", "graalvm"),
+    ("graalce", "graalvm"),
+    ("grl", "graalvm"),
+    ("ms", "microsoft"),
+    ("oracle", "oracle"),
+    ("sapmchn", "sapmachine"),
+    ("sem", "semeru"),
+];
+
+/// Rewrites an SDKMAN identifier such as `17.0.9-tem` as the equivalent
+/// `temurin-17`, leaving any other spec unchanged.
+fn from_sdkman(spec: &str) -> String {
+    let converted = split_distro_suffix(spec).and_then(|(version, distro)| {
+        let number = get_version_from_input(version)?.number?;
+        Some(match distro {
+            Some(distro) => format!("{}-{}", distro, number),
+            None => number.to_string(),
+        })
+    });
+    converted.unwrap_or_else(|| spec.to_string())
+}
+
+fn distro_identifiers(distro: &str) -> Vec<String> {
+    let distro = distro.to_lowercase();
+    match DISTRO_ALIASES
+        .iter()
+        .find(|group| group.contains(&distro.as_str()))
+    {
+        Some(group) => group.iter().map(|alias| alias.to_string()).collect(),
+        None => vec![distro],
+    }
+}
+
+/// Whether `identifier` starts a word of `field`, that is, appears at its
+/// start or after a character that isn't a letter or digit.
+fn starts_word(field: &str, identifier: &str) -> bool {
+    field.match_indices(identifier).any(|(i, _)| {
+        field[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric())
+    })
+}
+
+/// Whether `field` contains the pieces of `pattern` between `*`s in order,
+/// so that `temurin*17` matches `net.temurin.17.jdk`.
+fn glob_matches(field: &str, pattern: &str) -> bool {
+    let mut rest = field;
+    pattern.split('*').all(|piece| match rest.find(piece) {
+        Some(i) => {
+            rest = &rest[i + piece.len()..];
+            true
+        }
+        None => false,
+    })
+}
+
+/// Whether the JVM's bundle id, home or name has a word starting with one of
+/// the distro's identifiers, case-insensitively. Matching only at word starts
+/// keeps a short spec such as `z17` from matching the `z` inside `azul`.
+///
+/// A distro with a `*`, such as `graal*`, is instead a glob matched anywhere
+/// in the bundle id, home or vendor.
+fn is_distro(jvm: &Jvm, distro: &str) -> bool {
+    if distro.contains('*') {
+        let pattern = distro.to_lowercase();
+        return [&jvm.bundle_id, &jvm.home_path, &jvm.vendor]
+            .iter()
+            .any(|field| glob_matches(&field.to_lowercase(), &pattern));
+    }
+    let fields = [&jvm.bundle_id, &jvm.home_path, &jvm.name]
+        .map(|field| field.to_lowercase());
+    distro_identifiers(distro)
+        .iter()
+        .any(|identifier| fields.iter().any(|field| starts_word(field, identifier)))
+}
+
+fn distro_matches(v: &V, jvm: &Jvm) -> bool {
+    v.distro
+        .as_ref()
+        .is_none_or(|distro| is_distro(jvm, distro))
+}
+
+fn activate(selection: &Jvm, verbosity: Verbosity) {
+    let already_active =
+        env::var("JAVA_HOME").is_ok_and(|home| home == selection.home_path);
+    match verbosity {
+        Verbosity::Normal => {
+            println!("{}", selection.home_path);
+            eprintln!("Activating Java {}", selection.name);
+        }
+        Verbosity::Human => {
+            println!("Activated {} at {}", selection.name, selection.home_path)
+        }
+        // Quietly switching to the active JVM is a no-op, so print nothing
+        // and leave the shell hook with nothing to export.
+        Verbosity::Quiet | Verbosity::Silent if already_active => {}
+        Verbosity::Quiet | Verbosity::Silent => println!("{}", selection.home_path),
+    }
+}
+
+/// Gives a spec that names no distro, such as `17`, the distro of
+/// `JVMVJ_SPEC_PREFIX`, such as `temurin-`, to bias every such spec towards
+/// one. A spec naming its own distro, as in `17-zulu`, is left alone.
+fn with_spec_prefix(v: V, prefix: Option<&str>) -> V {
+    let prefix_distro = prefix
+        .and_then(|prefix| get_version_from_input(prefix.trim_end_matches('-')))
+        .and_then(|prefix| prefix.distro);
+    match (&v.distro, prefix_distro) {
+        (None, Some(distro)) => V {
+            distro: Some(distro),
+            ..v
+        },
+        _ => v,
+    }
+}
+
+/// Parses a whitespace-separated fallback chain of specs, such as asdf's
+/// `temurin-17 temurin-21`, in order of preference.
+fn get_versions_from_input(spec: &str) -> Option<Vec<V>> {
+    let prefix = env::var("JVMVJ_SPEC_PREFIX").ok();
+    let versions = spec
+        .split_whitespace()
+        .map(|spec| {
+            get_version_from_input(spec)
+                .map(|v| with_spec_prefix(v, prefix.as_deref()))
+        })
+        .collect::<Option<Vec<V>>>()?;
+    if versions.is_empty() {
+        None
+    } else {
+        Some(versions)
+    }
+}
+
+/// Compares version strings such as `17.0.9` and `1.8.0_302` component by
+/// component, numerically where both components are numbers and lexically
+/// otherwise. An extra trailing number, as in `17.0.9.1`, sorts after its
+/// prefix, but an extra pre-release tag, as in `21.0.1-ea`, sorts before it,
+/// and a number sorts after a tag in the same place.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    const SEPARATORS: [char; 4] = ['.', '-', '+', '_'];
+    let is_number = |part: &str| part.parse::<u64>().is_ok();
+    let mut a_parts = a.split(SEPARATORS);
+    let mut b_parts = b.split(SEPARATORS);
+    loop {
+        let ordering = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(b_part)) if is_number(b_part) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (Some(a_part), None) if is_number(a_part) => return Ordering::Greater,
+            (Some(_), None) => return Ordering::Less,
+            (Some(a_part), Some(b_part)) => {
+                match (a_part.parse::<u64>(), b_part.parse::<u64>()) {
+                    (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+                    (Ok(_), Err(_)) => Ordering::Greater,
+                    (Err(_), Ok(_)) => Ordering::Less,
+                    _ => a_part.cmp(b_part),
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+struct SelectOptions {
+    jdk_only: bool,
+    select_index: Option<usize>,
+    distro_priority: Vec<String>,
+    /// The configured `default_distro` for each major version.
+    default_distro: BTreeMap<String, String>,
+    exclude_distros: Vec<String>,
+    ci: bool,
+    post_switch: Option<String>,
+    /// The architecture to prefer, or `None` with `--arch all` to treat every
+    /// architecture alike.
+    native_arch: Option<String>,
+    /// The only architecture to select, from `.jvmvjrc`.
+    arch: Option<String>,
+    arch_aliases: BTreeMap<String, String>,
+    /// `--print-export`: print an `export JAVA_HOME=...` line to `eval`
+    /// instead of the bare path.
+    print_export: bool,
+    /// `--prefer-newest-distro`: ignore `default_distro` and
+    /// `distro_priority`, so that the newest full version wins whatever its
+    /// distro.
+    prefer_newest_distro: bool,
+    /// `--allow-ea`: rank early-access builds by version like any other,
+    /// rather than after every GA release.
+    allow_ea: bool,
+    /// `--json`: print a JSON object describing the selected JVM instead of
+    /// the bare path.
+    json: bool,
+}
+
+impl SelectOptions {
+    fn from_args(args: &[String], config: &config::Config) -> Self {
+        let select_index = flag_value(args, "--select-index").map(|index| {
+            index.parse::<usize>().unwrap_or_else(|_| {
+                exit_with_err(
+                    &format!("--select-index {} should be a number!", index),
+                    Verbosity::Normal,
+                )
+            })
+        });
+
+        SelectOptions {
+            jdk_only: has_flag(args, "--jdk-only"),
+            select_index,
+            distro_priority: config.distro_priority.clone(),
+            default_distro: config.default_distro.clone(),
+            exclude_distros: if has_flag(args, "--include-excluded") {
+                vec![]
+            } else {
+                config.exclude_distros.clone()
+            },
+            ci: has_flag(args, "--ci")
+                || env::var("CI").is_ok_and(|ci| ci == "true"),
+            post_switch: config.post_switch.clone(),
+            native_arch: (flag_value(args, "--arch") != Some("all"))
+                .then(|| normalize_arch(env::consts::ARCH, &config.arch_aliases)),
+            print_export: has_flag(args, "--print-export"),
+            arch: None,
+            arch_aliases: config.arch_aliases.clone(),
+            prefer_newest_distro: has_flag(args, "--prefer-newest-distro"),
+            allow_ea: has_flag(args, "--allow-ea"),
+            json: has_flag(args, "--json"),
+        }
+    }
+}
+
+/// A JDK tool such as `java` in the JVM home `home`, with `.exe` on Windows.
+fn tool_path(home: &str, tool: &str) -> PathBuf {
+    Path::new(home)
+        .join("bin")
+        .join(format!("{}{}", tool, env::consts::EXE_SUFFIX))
+}
+
+/// Whether the JVM is an early-access build, such as `21-ea`.
+fn is_early_access(jvm: &Jvm) -> bool {
+    jvm.version.to_lowercase().contains("-ea")
+}
+
+fn is_jdk(jvm: &Jvm) -> bool {
+    tool_path(&jvm.home_path, "javac").exists()
+}
+
+/// Position of the JVM's distro in the configured priority list, with
+/// unlisted distros after all listed ones.
+fn distro_rank(jvm: &Jvm, distro_priority: &[String]) -> usize {
+    distro_priority
+        .iter()
+        .position(|distro| {
+            jvm.bundle_id.contains(distro.as_str())
+                || jvm.home_path.contains(distro.as_str())
+        })
+        .unwrap_or(distro_priority.len())
+}
+
+/// How strongly the configuration prefers the JVM's distro, lowest first:
+/// the `default_distro` for its major version, then its position in
+/// `distro_priority`.
+fn preference_rank(jvm: &Jvm, options: &SelectOptions) -> (bool, usize) {
+    let is_default = options
+        .default_distro
+        .get(&jvm.major_version().to_string())
+        .is_some_and(|distro| is_distro(jvm, distro));
+    (!is_default, distro_rank(jvm, &options.distro_priority))
+}
+
+/// The JVMs matching `v`, in the order every consumer sees them:
+///
+/// 1. GA releases before early-access builds, unless `--allow-ea`;
+/// 2. the configured `default_distro` for the JVM's major version, then
+///    position in the configured `distro_priority`, unless
+///    `--prefer-newest-distro`;
+/// 3. full version, newest first;
+/// 4. native architecture before emulated ones, unless `--arch all`;
+/// 5. name, so that the order is the same on every run.
+///
+/// Without `--select-index`, the first candidate is the one selected.
+fn candidates<'a>(v: &V, jvms: &'a [Jvm], options: &SelectOptions) -> Vec<&'a Jvm> {
+    let mut candidates = jvms
+        .iter()
+        .filter(|jvm| {
+            v.number.is_none_or(|number| jvm.major_version() == number)
+                && distro_matches(v, jvm)
+        })
+        .filter(|jvm| !v.early_access || is_early_access(jvm))
+        .filter(|jvm| {
+            v.arch.as_ref().is_none_or(|arch| {
+                jvm.arch == normalize_arch(arch, &options.arch_aliases)
+            })
+        })
+        .filter(|jvm| !options.jdk_only || is_jdk(jvm))
+        .filter(|jvm| options.arch.as_ref().is_none_or(|arch| &jvm.arch == arch))
+        .filter(|jvm| {
+            !options
+                .exclude_distros
+                .iter()
+                .any(|distro| is_distro(jvm, distro))
+        })
+        .collect::<Vec<&Jvm>>();
+    candidates.sort_by(|a, b| {
+        let is_ea = |jvm: &Jvm| !options.allow_ea && is_early_access(jvm);
+        is_ea(a)
+            .cmp(&is_ea(b))
+            .then_with(|| {
+                if options.prefer_newest_distro {
+                    Ordering::Equal
+                } else {
+                    preference_rank(a, options).cmp(&preference_rank(b, options))
+                }
+            })
+            .then_with(|| compare_versions(&b.version, &a.version))
+            .then_with(|| {
+                let is_native =
+                    |jvm: &Jvm| options.native_arch.as_ref() == Some(&jvm.arch);
+                is_native(b).cmp(&is_native(a))
+            })
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    trace!(
+        "{:?} matches {} of {} JVMs: {:?}",
+        v,
+        candidates.len(),
+        jvms.len(),
+        candidates
+            .iter()
+            .map(|jvm| &jvm.name)
+            .collect::<Vec<&String>>()
+    );
+    candidates
+}
+
+fn find_jvm<'a>(v: &V, jvms: &'a [Jvm], options: &SelectOptions) -> Option<&'a Jvm> {
+    let candidates = candidates(v, jvms, options);
+    let index = options.select_index.unwrap_or(0);
+    if !candidates.is_empty() && index >= candidates.len() {
+        exit_with_err(
+            &format!(
+                "--select-index {} is out of range; only {} JVMs match",
+                index,
+                candidates.len()
+            ),
+            Verbosity::Normal,
+        );
+    }
+    candidates.get(index).copied()
+}
+
+fn resolve<'a>(
+    versions: &[V],
+    jvms: &'a [Jvm],
+    options: &SelectOptions,
+) -> Option<&'a Jvm> {
+    versions.iter().find_map(|v| find_jvm(v, jvms, options))
+}
+
+/// The installed major versions closest to `number`; two when it falls
+/// exactly between them.
+fn nearest_majors(number: u16, jvms: &[Jvm]) -> Vec<u16> {
+    let mut majors = jvms.iter().map(Jvm::major_version).collect::<Vec<u16>>();
+    majors.sort();
+    majors.dedup();
+
+    let Some(distance) = majors.iter().map(|major| major.abs_diff(number)).min()
+    else {
+        return vec![];
+    };
+    majors
+        .into_iter()
+        .filter(|major| major.abs_diff(number) == distance)
+        .collect()
+}
+
+fn not_found_message(spec: &str, versions: &[V], jvms: &[Jvm]) -> String {
+    let Some(number) = versions[0].number else {
+        return format!("No {} JVM is installed!", spec);
+    };
+    let suggestions = nearest_majors(number, jvms)
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<String>>();
+    if suggestions.is_empty() {
+        format!(
+            "You requested a JVM of version {}, but no JVMs are installed!",
+            spec
+        )
+    } else {
+        format!(
+            "{} not found; did you mean {}?",
+            spec,
+            suggestions.join(" or ")
+        )
+    }
+}
+
+/// The `current+` spec: the major version of the active `JAVA_HOME`, so that
+/// selecting it picks up the newest installed patch.
+fn current_major(jvms: &[Jvm]) -> Option<V> {
+    let java_home = env::var("JAVA_HOME").ok()?;
+    let jvm = current_jvm(&java_home, jvms)?;
+    Some(V {
+        number: Some(jvm.major_version()),
+        distro: None,
+        early_access: false,
+        arch: None,
+    })
+}
+
+/// Why a spec selects nothing: the message, and the exit status to fail with.
+struct SelectError {
+    message: String,
+    code: i32,
+}
+
+/// The JVM `spec` selects, the same way for every command that takes one:
+/// the JDK at an asdf-style `path:`, the runner's `JAVA_HOME_<N>` in CI mode,
+/// or an installed JVM, including for `current+`.
+fn try_select<'a>(
+    spec: &str,
+    jvms: &'a [Jvm],
+    options: &SelectOptions,
+) -> Result<Cow<'a, Jvm>, SelectError> {
+    if let Some(path) = spec.strip_prefix("path:") {
+        let jvm = discovery::jvm_at_path(Path::new(path));
+        if !tool_path(&jvm.home_path, "java").exists() {
+            return Err(SelectError {
+                message: format!(
+                    "{} is not a JVM home; it has no bin/java",
+                    jvm.home_path
+                ),
+                code: EXIT_NOT_FOUND,
+            });
+        }
+        return Ok(Cow::Owned(jvm));
+    }
+    let versions = if spec == "current+" {
+        Some(vec![current_major(jvms).ok_or_else(|| SelectError {
+            message: "current+ needs JAVA_HOME to be a JVM known to java_home!"
+                .to_string(),
+            code: 1,
+        })?])
+    } else {
+        get_versions_from_input(spec)
+    };
+    let Some(versions) = versions else {
+        return Err(SelectError {
+            message: format!("Did not understand version spec {}", spec),
+            code: 1,
+        });
+    };
+    if options.ci {
+        let from_env = versions
+            .iter()
+            .find_map(|version| ci_java_home(version.number?));
+        if let Some(jvm) = from_env {
+            return Ok(Cow::Owned(jvm));
+        }
+    }
+    match resolve(&versions, jvms, options) {
+        Some(jvm) => Ok(Cow::Borrowed(jvm)),
+        None => Err(SelectError {
+            message: not_found_message(spec, &versions, jvms),
+            code: EXIT_NOT_FOUND,
+        }),
+    }
+}
+
+fn select<'a>(
+    spec: &str,
+    jvms: &'a [Jvm],
+    options: &SelectOptions,
+    verbosity: Verbosity,
+) -> Cow<'a, Jvm> {
+    try_select(spec, jvms, options)
+        .unwrap_or_else(|err| exit_with_code(&err.message, verbosity, err.code))
+}
+
+fn switch_to(
+    spec: &str,
+    jvms: &[Jvm],
+    options: &SelectOptions,
+    verbosity: Verbosity,
+) -> String {
+    let selection = select(spec, jvms, options, verbosity);
+    warn_if_jre(&selection, verbosity);
+    switch(&selection, options, verbosity);
+    selection.home_path.clone()
+}
+
+fn warn_if_jre(selection: &Jvm, verbosity: Verbosity) {
+    if verbosity == Verbosity::Normal && !is_jdk(selection) {
+        eprintln!("Warning: selected JVM appears to be a JRE; javac not found");
+    }
+}
+
+/// Warns when `auto` picks between several JVMs of the same major version only
+/// by version and name, as nothing configured tells the top two apart.
+fn warn_if_ambiguous(spec: &str, jvms: &[Jvm], options: &SelectOptions) {
+    let Some(versions) = get_versions_from_input(spec) else {
+        return;
+    };
+    let Some((v, matches)) = versions
+        .iter()
+        .map(|v| (v, candidates(v, jvms, options)))
+        .find(|(_, matches)| !matches.is_empty())
+    else {
+        return;
+    };
+    if v.number.is_none() || v.distro.is_some() || options.prefer_newest_distro {
+        return;
+    }
+    let tied = match matches[..] {
+        [first, second, ..] => {
+            preference_rank(first, options) == preference_rank(second, options)
+        }
+        _ => false,
+    };
+    if tied {
+        eprintln!(
+            "Hint: {} JVMs match {} ({}); set distro_priority or default_distro in the config, or name a distro in the version file, to choose one",
+            matches.len(),
+            spec,
+            matches
+                .iter()
+                .map(|jvm| jvm.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+    }
+}
+
+/// Prints `<spec>\t<home>` for each spec, or `<spec>\tMISSING` for one that
+/// doesn't resolve. Fails once all are printed if any were missing, or at the
+/// first missing one with `--fail-fast`.
+fn resolve_many(
+    specs: &[&String],
+    jvms: &[Jvm],
+    options: &SelectOptions,
+    fail_fast: bool,
+    verbosity: Verbosity,
+) {
+    let mut missing = false;
+    for spec in specs {
+        match try_select(spec, jvms, options) {
+            Ok(jvm) => println!("{}\t{}", spec, jvm.home_path),
+            Err(_) => {
+                println!("{}\tMISSING", spec);
+                missing = true;
+                if fail_fast {
+                    break;
+                }
+            }
+        }
+    }
+    if missing {
+        exit_with_code("Some specs did not resolve", verbosity, EXIT_NOT_FOUND)
+    }
+}
+
+/// Activates `selection`, then runs the configured `post_switch` command if
+/// that changed `JAVA_HOME`. The command's output goes to stderr, as stdout
+/// is the path the shell function exports, or nowhere when quiet.
+fn switch(selection: &Jvm, options: &SelectOptions, verbosity: Verbosity) {
+    let changed =
+        env::var("JAVA_HOME").map_or(true, |home| home != selection.home_path);
+    if options.json {
+        let major = parse_major_version(&selection.version)
+            .or_else(|_| parse_major_version(&selection.platform_version))
+            .ok();
+        println!(
+            "{}",
+            json!({
+                "home": selection.home_path,
+                "name": selection.name,
+                "version": selection.version,
+                "major": major,
+                "arch": selection.arch,
+                "vendor": selection.vendor,
+            })
+        );
+    } else if options.print_export {
+        println!("export JAVA_HOME={}", shell_quote(&selection.home_path));
+        if verbosity == Verbosity::Normal {
+            eprintln!("Activating Java {}", selection.name);
+        }
+    } else {
+        activate(selection, verbosity);
+    }
+
+    let Some(command) = options.post_switch.as_deref().filter(|_| changed) else {
+        return;
+    };
+    let mut hook = Command::new("sh");
+    hook.arg("-c")
+        .arg(command)
+        .env("JVMVJ_JAVA_HOME", &selection.home_path);
+    if let Verbosity::Quiet | Verbosity::Silent = verbosity {
+        hook.stdout(Stdio::null()).stderr(Stdio::null());
+    } else {
+        hook.stdout(io::stderr());
+    }
+    let status = hook.status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) if verbosity == Verbosity::Normal => {
+            eprintln!("Warning: post_switch command exited with {}", status)
+        }
+        Err(err) if verbosity == Verbosity::Normal => {
+            eprintln!("Warning: failed to run post_switch command: {}", err)
+        }
+        _ => {}
+    }
+}
+
+/// Prints every field of the selected JVM, its major version and version
+/// components, one `key: value` line each.
+fn print_info(
+    spec: &str,
+    jvms: &[Jvm],
+    options: &SelectOptions,
+    verbosity: Verbosity,
+) {
+    let selection = select(spec, jvms, options, verbosity);
+    let mut fields = vec![
+        ("arch", selection.arch.clone()),
+        ("bundle_id", selection.bundle_id.clone()),
+        ("enabled", selection.enabled.to_string()),
+        ("home_path", selection.home_path.clone()),
+        ("name", selection.name.clone()),
+        ("platform_version", selection.platform_version.clone()),
+        ("vendor", selection.vendor.clone()),
+        ("version", selection.version.clone()),
+        ("major", selection.major_version().to_string()),
+    ];
+    if let Some(components) = selection.version_components() {
+        fields.extend([
+            ("feature", components.feature.to_string()),
+            ("interim", components.interim.to_string()),
+            ("update", components.update.to_string()),
+            ("patch", components.patch.to_string()),
+        ]);
+    }
+    for (key, value) in fields {
+        println!("{:<18}{}", format!("{}:", key), value);
+    }
+}
+
+/// Prints the selected JVM's `bin` directory, for tools that put it on
+/// `PATH` directly rather than going through `JAVA_HOME`.
+fn print_bin(
+    spec: &str,
+    jvms: &[Jvm],
+    options: &SelectOptions,
+    verbosity: Verbosity,
+) {
+    let selection = select(spec, jvms, options, verbosity);
+    let bin = Path::new(&selection.home_path).join("bin");
+    if !bin.is_dir() {
+        exit_with_err(
+            &format!(
+                "{} has no bin directory at {}",
+                selection.name,
+                bin.display()
+            ),
+            verbosity,
+        );
+    }
+    println!("{}", bin.display());
+}
+
+/// A JDK exported by the CI runner, as setup-java does with
+/// `JAVA_HOME_17_X64`, falling back to a plain `JAVA_HOME_17`.
+fn ci_java_home(major: u16) -> Option<Jvm> {
+    let arch = match env::consts::ARCH {
+        "x86_64" => "X64".to_string(),
+        "aarch64" => "ARM64".to_string(),
+        other => other.to_uppercase(),
+    };
+    [
+        format!("JAVA_HOME_{}_{}", major, arch),
+        format!("JAVA_HOME_{}", major),
+    ]
+    .iter()
+    .filter_map(env::var_os)
+    .map(|home| discovery::jvm_at_path(Path::new(&home)))
+    .find(|jvm| tool_path(&jvm.home_path, "java").exists())
+}
+
+/// Runs `command` with `JAVA_HOME` pointing at the selected JVM and its `bin`
+/// directory first on `PATH`, exiting with the command's exit status.
+fn exec_with(
+    spec: &str,
+    command: &[String],
+    jvms: &[Jvm],
+    options: &SelectOptions,
+) -> ! {
+    let selection = select(spec, jvms, options, Verbosity::Normal);
+    let bin = Path::new(&selection.home_path).join("bin");
+    let mut paths = vec![bin];
+    if let Some(path) = env::var_os("PATH") {
+        paths.extend(env::split_paths(&path));
+    }
+
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .env("JAVA_HOME", &selection.home_path)
+        .env("PATH", env::join_paths(paths).unwrap())
+        .status()
+        .unwrap_or_else(|err| {
+            exit_with_err(
+                &format!("Failed to run {}: {}", command[0], err),
+                Verbosity::Normal,
+            )
+        });
+
+    exit(status.code().unwrap_or(1))
+}
+
+fn switch_to_dir_name(dir_name: &str, jvms: &[Jvm]) {
+    let selection = jvms
+        .iter()
+        .find(|jvm| jvm.home_path.contains(dir_name))
+        .unwrap_or_else(|| {
+            exit_with_err(
+                &format!(
+                    "No installed JVM has a home path containing {}!",
+                    dir_name
+                ),
+                Verbosity::Normal,
+            )
+        });
+
+    activate(selection, Verbosity::Normal);
+}
+
+/// The discovered JVM that `JAVA_HOME` currently points at, if any.
+fn current_jvm<'a>(java_home: &str, jvms: &'a [Jvm]) -> Option<&'a Jvm> {
+    let java_home = java_home.trim_end_matches('/');
+    jvms.iter()
+        .find(|jvm| jvm.home_path.trim_end_matches('/') == java_home)
+}
+
+fn show_current(jvms: &[Jvm], json: bool, porcelain: bool, quiet: bool) {
+    let java_home = env::var("JAVA_HOME").ok();
+    let jvm = java_home
+        .as_deref()
+        .and_then(|java_home| current_jvm(java_home, jvms));
+
+    if json {
+        println!(
+            "{}",
+            json!({
+                "java_home": java_home,
+                "name": jvm.map(|jvm| &jvm.name),
+                "version": jvm.map(|jvm| &jvm.version),
+                "major": jvm.map(Jvm::major_version),
+                "managed": jvm.is_some(),
+            })
+        );
+        return;
+    }
+
+    // Only a JAVA_HOME that is a known JVM has a row to print.
+    if porcelain {
+        println!("{}", PORCELAIN_HEADER);
+        if let Some(jvm) = jvm {
+            println!("{}", porcelain_row(jvm));
+        }
+        return;
+    }
+
+    // Just the major version for a prompt, and nothing at all when there's
+    // no known JVM to show.
+    if quiet {
+        if let Some(jvm) = jvm {
+            println!("{}", jvm.major_version());
+        }
+        return;
+    }
+
+    match (java_home, jvm) {
+        (None, _) => println!("JAVA_HOME is not set"),
+        (Some(java_home), None) => {
+            println!("{} (not a JVM known to java_home)", java_home)
+        }
+        (Some(java_home), Some(jvm)) => println!("{} at {}", jvm.name, java_home),
+    }
+}
+
+fn switch_to_version_regex(pattern: &str, jvms: &[Jvm], verbosity: Verbosity) {
+    let regex = Regex::new(pattern).unwrap_or_else(|err| {
+        exit_with_err(
+            &format!("Invalid version regex {}: {}", pattern, err),
+            verbosity,
+        )
+    });
+    let selection = jvms
+        .iter()
+        .filter(|jvm| regex.is_match(&jvm.version))
+        .max_by(|a, b| compare_versions(&a.version, &b.version))
+        .unwrap_or_else(|| {
+            exit_with_code(
+                &format!("No installed JVM has a version matching {}!", pattern),
+                verbosity,
+                EXIT_NOT_FOUND,
+            )
+        });
+
+    activate(selection, verbosity);
+}
+
+#[cfg(unix)]
+fn java_binary_is_executable(jvm: &Jvm) -> bool {
+    fs::metadata(tool_path(&jvm.home_path, "java"))
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn java_binary_is_executable(jvm: &Jvm) -> bool {
+    tool_path(&jvm.home_path, "java").is_file()
+}
+
+/// Whether the JVM's home is still on disk, as `java_home` can list JVMs
+/// that have since been deleted.
+fn is_installed(jvm: &Jvm) -> bool {
+    Path::new(&jvm.home_path).exists()
+}
+
+fn doctor(jvms: &[Jvm]) {
+    let broken = jvms
+        .iter()
+        .filter(|jvm| !java_binary_is_executable(jvm))
+        .collect::<Vec<&Jvm>>();
+
+    for jvm in &broken {
+        if is_installed(jvm) {
+            println!(
+                "{} at {} has no executable bin/java",
+                jvm.name, jvm.home_path
+            );
+        } else {
+            println!(
+                "{} at {} no longer exists; `jvmvj list --installed-only` hides it",
+                jvm.name, jvm.home_path
+            );
+        }
+    }
+    println!(
+        "{} healthy, {} broken",
+        jvms.len() - broken.len(),
+        broken.len()
+    );
+
+    if !broken.is_empty() {
+        exit(1)
+    }
+}
+
+/// A spec's major version and distro, or `None` if it doesn't parse.
+type ParsedSpec<'a> = Option<(Option<u16>, Option<&'a str>)>;
+
+/// Specs and how `--selftest` expects them to parse.
+const SPEC_VECTORS: [(&str, ParsedSpec); 14] = [
+    ("17", Some((Some(17), None))),
+    ("1.8", Some((Some(8), None))),
+    ("21.0.1", Some((Some(21), None))),
+    ("temurin-17", Some((Some(17), Some("temurin")))),
+    ("zulu17", Some((Some(17), Some("zulu")))),
+    ("openjdk-11", Some((Some(11), None))),
+    ("temurin", Some((None, Some("temurin")))),
+    ("openjdk64-17.0.9", Some((Some(17), None))),
+    ("17.0.9-librca", Some((Some(17), Some("liberica")))),
+    ("21-ea", Some((Some(21), None))),
+    ("temurin*17", Some((Some(17), Some("temurin*")))),
+    ("temurin-17@aarch64", Some((Some(17), Some("temurin")))),
+    ("17x", None),
+    ("", None),
+];
+
+/// Pairs of versions and how `--selftest` expects them to compare.
+const VERSION_VECTORS: [(&str, &str, Ordering); 5] = [
+    ("17.0.10", "17.0.9", Ordering::Greater),
+    ("1.8.0_302", "1.8.0_292", Ordering::Greater),
+    ("11", "11.0.1", Ordering::Less),
+    ("21.0.1", "21.0.1", Ordering::Equal),
+    ("17.0.9", "21", Ordering::Less),
+];
+
+/// Runs the spec parser and version comparator against known inputs, to
+/// confirm they behave on this machine when triaging a bug.
+fn selftest() -> ! {
+    let mut failures = 0;
+    let mut report = |passed: bool, description: String| {
+        println!("{} {}", if passed { "PASS" } else { "FAIL" }, description);
+        if !passed {
+            failures += 1;
+        }
+    };
+
+    for (spec, expected) in SPEC_VECTORS {
+        let parsed = get_version_from_input(spec)
+            .map(|v| (v.number, v.distro.map(|distro| distro.to_lowercase())));
+        let expected =
+            expected.map(|(number, distro)| (number, distro.map(str::to_string)));
+        report(
+            parsed == expected,
+            format!(
+                "spec {:?} parses to {:?}, expected {:?}",
+                spec, parsed, expected
+            ),
+        );
+    }
+    for (a, b, expected) in VERSION_VECTORS {
+        let ordering = compare_versions(a, b);
+        report(
+            ordering == expected,
+            format!("{} is {:?} {}, expected {:?}", a, ordering, b, expected),
+        );
+    }
+
+    exit(if failures == 0 { 0 } else { 1 })
+}
+
+/// Writes the java entry of this directory's `.tool-versions` to a new
+/// `.java-version`, for users moving off asdf. The original is left alone.
+fn migrate(verbosity: Verbosity) {
+    let dir = current_dir(verbosity);
+    let spec = find_version_string_from_tool_versions(&dir.join(".tool-versions"))
+        .filter(|spec| !spec.is_empty())
+        .unwrap_or_else(|| {
+            exit_with_err("No java entry found in .tool-versions!", verbosity)
+        });
+    let spec = spec
+        .split_whitespace()
+        .map(from_sdkman)
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    let path = dir.join(".java-version");
+    if path.exists() {
+        exit_with_err(&format!("{} already exists!", path.display()), verbosity);
+    }
+    if let Err(err) = fs::write(&path, format!("{}\n", spec)) {
+        exit_with_err(
+            &format!("Failed to write {}: {}", path.display(), err),
+            verbosity,
+        );
+    }
+    println!("Wrote {} to {}", spec, path.display());
+}
+
+/// Writes `spec` to `.java-version` in the current directory, once it's known
+/// to select an installed JVM. With `--dry-run`, only prints what would be
+/// written where.
+fn pin(
+    spec: &str,
+    jvms: &[Jvm],
+    options: &SelectOptions,
+    dry_run: bool,
+    verbosity: Verbosity,
+) {
+    let selection = select(spec, jvms, options, verbosity);
+    let path = current_dir(verbosity).join(".java-version");
+    if dry_run {
+        println!(
+            "Would write {} to {}, selecting {}",
+            spec,
+            path.display(),
+            selection.name
+        );
+        return;
+    }
+    if let Err(err) = fs::write(&path, format!("{}\n", spec)) {
+        exit_with_err(
+            &format!("Failed to write {}: {}", path.display(), err),
+            verbosity,
+        );
+    }
+    println!("Wrote {} to {}", spec, path.display());
+}
+
+fn find_version_string_from_tool_versions(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let java_line = contents
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| l.starts_with("java"))?;
+
+    let specs = java_line.split_whitespace().skip(1).collect::<Vec<&str>>();
+    Some(specs.join(" "))
+}
+
+/// The `java=` entry of an SDKMAN `.sdkmanrc`, which may also pin other
+/// tools and contain `#` comments.
+fn find_version_string_from_sdkmanrc(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    contents
+        .lines()
+        .map(|line| {
+            line.split_once('#')
+                .map_or(line, |(before, _)| before)
+                .trim()
+        })
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "java")
+        .map(|(_, spec)| spec.trim().to_string())
+}
+
+/// How many `include`s deep a version file may go.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Whether a plain version file names its spec with `include <path>`.
+fn is_include(contents: &str) -> bool {
+    contents.trim().starts_with("include ")
+}
+
+/// The spec in a plain version file. One containing `include <path>` instead
+/// uses the spec of the file at that path, relative to its own directory, so
+/// that projects can share a version file.
+fn read_version_file(path: &Path, verbosity: Verbosity) -> String {
+    let read = |path: &Path| {
+        fs::read_to_string(path).unwrap_or_else(|err| {
+            exit_with_err(
+                &format!("Failed to read {}: {}", path.display(), err),
+                verbosity,
+            )
+        })
+    };
+    let canonical =
+        |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    let mut path = path.to_path_buf();
+    let mut included_from = vec![];
+    loop {
+        let contents = read(&path);
+        if !is_include(&contents) {
+            return contents.trim().to_string();
+        }
+        let target = contents.trim()["include ".len()..].trim();
+        let next = path.parent().unwrap_or(Path::new("")).join(target);
+        trace!("{} includes {}", path.display(), next.display());
+        included_from.push(canonical(&path));
+        if included_from.contains(&canonical(&next)) {
+            exit_with_err(
+                &format!("{} includes itself!", next.display()),
+                verbosity,
+            );
+        }
+        if included_from.len() > MAX_INCLUDE_DEPTH {
+            exit_with_err(
+                &format!(
+                    "{} is more than {} includes deep!",
+                    next.display(),
+                    MAX_INCLUDE_DEPTH
+                ),
+                verbosity,
+            );
+        }
+        path = next;
+    }
+}
+
+/// The first of `version_files` in `dir` naming a spec, and that spec.
+fn find_version_file_in_dir(
+    dir: &Path,
+    version_files: &[String],
+    verbosity: Verbosity,
+) -> Option<(PathBuf, String)> {
+    version_files.iter().find_map(|file_name| {
+        let path = dir.join(file_name);
+        trace!("checking {}", path.display());
+        let spec = if file_name == ".tool-versions" {
+            find_version_string_from_tool_versions(&path)
+        } else if file_name == ".sdkmanrc" {
+            find_version_string_from_sdkmanrc(&path)
+        } else if path.is_file() {
+            Some(read_version_file(&path, verbosity))
+        } else {
+            None
+        };
+        if let Some(spec) = &spec {
+            trace!("found spec {:?} in {}", spec, path.display());
+            if spec.is_empty() {
+                exit_with_err(
+                    &format!("{} doesn't name a Java version!", path.display()),
+                    verbosity,
+                );
+            }
+        }
+        Some((path, spec?))
+    })
+}
+
+/// How far `auto` looks above the current directory.
+#[derive(Clone, PartialEq)]
+enum Walk {
+    /// Every parent up to the filesystem root.
+    ToRoot,
+    /// A directory containing any of these markers is the last one checked,
+    /// so a project never inherits a version from above it. These are the
+    /// configured `boundary_markers`, plus `.git` with `--git-root`.
+    ToBoundary(Vec<String>),
+    /// `--no-walk`: only the current directory.
+    Here,
+}
+
+impl Walk {
+    fn from_args(args: &[String], config: &config::Config) -> Self {
+        if has_flag(args, "--no-walk") {
+            return Walk::Here;
+        }
+        let mut markers = config.boundary_markers.clone();
+        if (config.git_root || has_flag(args, "--git-root"))
+            && !markers.iter().any(|marker| marker == ".git")
+        {
+            markers.push(".git".to_string());
+        }
+        if markers.is_empty() {
+            Walk::ToRoot
+        } else {
+            Walk::ToBoundary(markers)
+        }
+    }
+
+    /// Whether `dir` is the last directory to check.
+    fn stops_at(&self, dir: &Path) -> bool {
+        match self {
+            Walk::ToRoot => false,
+            Walk::ToBoundary(markers) => {
+                markers.iter().any(|marker| dir.join(marker).exists())
+            }
+            Walk::Here => true,
+        }
+    }
+}
+
+/// Walks up from `dir` until `find` returns a spec.
+fn find_upwards<T>(
+    dir: &Path,
+    walk: &Walk,
+    find: &impl Fn(&Path) -> Option<T>,
+) -> Option<T> {
+    if let Some(spec) = find(dir) {
+        Some(spec)
+    } else if walk.stops_at(dir) {
+        None
+    } else {
+        find_upwards(dir.parent()?, walk, find)
+    }
+}
+
+/// The version files `auto` looks for: just the one named by `--only`, such
+/// as `java-version`, or the configured ones.
+fn auto_version_files(args: &[String], config: &config::Config) -> Vec<String> {
+    match flag_value(args, "--only") {
+        Some(file) => vec![format!(".{}", file.trim_start_matches('.'))],
+        None => config.version_files.clone(),
+    }
+}
+
+/// The environment variables that change what `auto` chooses, beyond those
+/// that change the JVMs discovered.
+const RESOLUTION_ENV: [&str; 1] = ["JVMVJ_SPEC_PREFIX"];
+
+/// The files whose changes could change what `auto` chooses in `here`: the
+/// config, and every version file and `.jvmvjrc` that the walk could find.
+fn resolution_files(
+    here: &Path,
+    walk: &Walk,
+    args: &[String],
+    config: &config::Config,
+    config_path: Option<&Path>,
+) -> Vec<PathBuf> {
+    let names = auto_version_files(args, config);
+    let mut files = config_path
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect::<Vec<PathBuf>>();
+    let mut dir = Some(here);
+    while let Some(current) = dir {
+        for name in names.iter().map(String::as_str).chain([".jvmvjrc"]) {
+            files.push(current.join(name));
+        }
+        dir = current.parent().filter(|_| !walk.stops_at(current));
+    }
+    files
+}
+
+/// Where `auto` found the spec for a directory.
+enum SpecSource {
+    VersionFile(PathBuf),
+    Rc,
+    Remembered,
+    Gradle,
+    Maven,
+    Dockerfile,
+}
+
+impl SpecSource {
+    fn describe(&self) -> String {
+        match self {
+            SpecSource::VersionFile(path) => path.display().to_string(),
+            SpecSource::Rc => "the version in .jvmvjrc".to_string(),
+            SpecSource::Remembered => {
+                "the spec remembered for this directory".to_string()
+            }
+            SpecSource::Gradle => "the Gradle toolchain".to_string(),
+            SpecSource::Maven => "the Maven compiler release".to_string(),
+            SpecSource::Dockerfile => "the Dockerfile base image".to_string(),
+        }
+    }
+}
+
+/// What `auto` reads for a directory, before resolving anything.
+struct AutoLookup {
+    found: Option<(SpecSource, String)>,
+    /// The only architecture `.jvmvjrc` allows, if it names one.
+    arch: Option<String>,
+    /// The version files looked for, for the error when there is no spec.
+    version_files: Vec<String>,
+}
+
+/// Finds the spec `auto` uses in `here`: the nearest version file, then the
+/// `.jvmvjrc` version, then a spec remembered with `use --remember`, then
+/// whichever of `--gradle`, `--maven` and `--dockerfile` are given.
+/// `--only java-version` reads just `.java-version`, with none of the
+/// fallbacks after version files.
+fn find_auto_spec(
+    here: &Path,
+    args: &[String],
+    config: &config::Config,
+    walk: &Walk,
+    verbosity: Verbosity,
+) -> AutoLookup {
+    let rc = rc::load(here, walk, verbosity);
+    let arch = rc
+        .arch
+        .map(|arch| normalize_arch(&arch, &config.arch_aliases));
+    let only = flag_value(args, "--only").is_some();
+    let version_files = auto_version_files(args, config);
+    let found = find_upwards(here, walk, &|dir| {
+        find_version_file_in_dir(dir, &version_files, verbosity)
+    })
+    .map(|(path, spec)| (SpecSource::VersionFile(path), spec));
+    let heuristic = |flag, source, find: fn(&Path) -> Option<String>| {
+        has_flag(args, flag)
+            .then(|| find_upwards(here, walk, &find))?
+            .map(|spec| (source, spec))
+    };
+    let found = if only {
+        found
+    } else {
+        found
+            .or_else(|| rc.version.map(|spec| (SpecSource::Rc, spec)))
+            .or_else(|| {
+                cache::remembered_spec(here)
+                    .map(|spec| (SpecSource::Remembered, spec))
+            })
+            .or_else(|| {
+                heuristic("--gradle", SpecSource::Gradle, heuristics::gradle_version)
+            })
+            .or_else(|| {
+                heuristic("--maven", SpecSource::Maven, heuristics::maven_version)
+            })
+            .or_else(|| {
+                heuristic(
+                    "--dockerfile",
+                    SpecSource::Dockerfile,
+                    heuristics::dockerfile_version,
+                )
+            })
+    };
+    AutoLookup {
+        found,
+        arch,
+        version_files,
+    }
+}
+
+fn no_version_file(version_files: &[String], verbosity: Verbosity) -> ! {
+    exit_with_err(
+        &format!(
+            "No version file ({}) found in this directory or any parent!",
+            version_files.join(", ")
+        ),
+        verbosity,
+    )
+}
+
+/// Prints each step `auto` takes to choose a JVM for the current directory:
+/// where the spec came from, the spec, how that parses, and the candidates
+/// for each spec in the chain.
+fn explain(jvms: &[Jvm], lookup: AutoLookup, options: &SelectOptions) {
+    let verbosity = Verbosity::Normal;
+    let Some((source, spec)) = lookup.found else {
+        no_version_file(&lookup.version_files, verbosity)
+    };
+    println!("Spec from: {}", source.describe());
+    if let Some(arch) = &options.arch {
+        println!("Architecture: {} only, from .jvmvjrc", arch);
+    }
+    println!("Spec: {}", spec);
+
+    let Some(versions) = get_versions_from_input(&spec) else {
+        exit_with_err(
+            &format!("Did not understand version spec {}", spec),
+            verbosity,
+        );
+    };
+    for v in &versions {
+        let major = v
+            .number
+            .map_or("any".to_string(), |number| number.to_string());
+        let distro = v.distro.as_deref().unwrap_or("any");
+        println!("Parsed: major {}, distro {}", major, distro);
+        let candidates = candidates(v, jvms, options);
+        if candidates.is_empty() {
+            println!("  no installed JVM matches");
+        }
+        for (i, jvm) in candidates.iter().enumerate() {
+            println!(
+                "  {}. {} ({}) at {}",
+                i + 1,
+                jvm.name,
+                jvm.arch,
+                jvm.home_path
+            );
+        }
+    }
+
+    match resolve(&versions, jvms, options) {
+        Some(jvm) => println!("Selected: {} at {}", jvm.name, jvm.home_path),
+        None => println!("Selected: nothing"),
+    }
+}
+
+/// Exit status used when `java_home` could not report any installed JVMs.
+const EXIT_NO_JVMS: i32 = 2;
+/// Exit status used when a well-formed spec matches no installed JVM.
+const EXIT_NOT_FOUND: i32 = 3;
+
+/// Set when `--json` is given, so that failures are reported as a JSON
+/// object on stdout rather than a message on stderr.
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Set by `-vv`, unless quiet, to print the traces from `trace!`.
+static TRACE: AtomicBool = AtomicBool::new(false);
+
+/// The verbosity of this invocation, for failures raised where it isn't
+/// passed down. `--quiet` and `--silent` always win, so that nothing reaches
+/// the prompt from the shell hook.
+static INVOCATION_VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+
+#[derive(Clone, Copy, PartialEq)]
+enum Verbosity {
+    /// The selected path on stdout, with activation messages, warnings and
+    /// errors on stderr.
+    Normal,
+    /// `--quiet`: only the selected path. Failures exit successfully so that
+    /// the shell hook never complains when changing directory.
+    Quiet,
+    /// `--silent`: only the selected path. Failures print nothing but keep
+    /// their exit status.
+    Silent,
+    /// `--human`: a single friendly line on stdout, for running the binary
+    /// directly rather than through the shell function.
+    Human,
+}
+
+impl Verbosity {
+    /// `--silent` and `--quiet` win over `--human`, whatever the order.
+    fn from_args(args: &[String]) -> Self {
+        if has_flag(args, "--silent") {
+            Verbosity::Silent
+        } else if options(args)
+            .iter()
+            .any(|arg| arg == "-q" || arg == "--quiet")
+        {
+            Verbosity::Quiet
+        } else if has_flag(args, "--human") {
+            Verbosity::Human
+        } else {
+            Verbosity::Normal
+        }
+    }
+}
+
+fn exit_with_err(msg: &str, verbosity: Verbosity) -> ! {
+    exit_with_code(msg, verbosity, 1)
+}
+
+fn exit_with_code(msg: &str, verbosity: Verbosity, code: i32) -> ! {
+    let verbosity = match INVOCATION_VERBOSITY.get() {
+        Some(&quiet @ (Verbosity::Quiet | Verbosity::Silent)) => quiet,
+        _ => verbosity,
+    };
+    match verbosity {
+        Verbosity::Quiet => exit(0),
+        Verbosity::Silent => exit(code),
+        Verbosity::Normal | Verbosity::Human
+            if JSON_ERRORS.load(AtomicOrdering::Relaxed) =>
+        {
+            println!("{}", json!({ "error": msg, "code": code }));
+            exit(code)
+        }
+        Verbosity::Normal | Verbosity::Human => {
+            eprintln!("{}", msg);
+            exit(code)
+        }
+    }
+}
+
+/// Single-quotes `value` for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn is_shell_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn display_zsh_init(function_name: &str) {
+    if !is_shell_identifier(function_name) {
+        exit_with_err(
+            &format!("{} is not a valid shell function name!", function_name),
+            Verbosity::Normal,
+        );
+    }
+
+    let binr = env::current_exe().unwrap();
+    let bin = binr.display();
+    println!(
+        r#"
+{function_name}() {{
+    if [[ -n "$1" ]]; then
+        local located="$({bin} $1)"
+        if [[ -n "$located" ]]; then
+            export JAVA_HOME="$located"
+        fi
+    else
+        {bin}
+    fi
+}}
+autoload -U add-zsh-hook
+_{function_name}_cd_hook() {{
+    local located="$({bin} auto --quiet)"
+    if [[ -n "$located" ]]; then
+        export JAVA_HOME="$located"
+    fi
+}}
+add-zsh-hook chpwd _{function_name}_cd_hook
+"#
+    );
+}
+
+/// How long to wait for `java_home`, so that a hung call can't freeze every
+/// `cd` through the shell hook. Overridable in seconds with `JVMVJ_TIMEOUT`.
+fn java_home_timeout() -> Duration {
+    env::var("JVMVJ_TIMEOUT")
+        .ok()
+        .and_then(|timeout| timeout.parse::<f64>().ok())
+        .and_then(|timeout| Duration::try_from_secs_f64(timeout).ok())
+        .unwrap_or(Duration::from_secs(3))
+}
+
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+struct JavaHomeOutput {
+    status: ExitStatus,
+    stderr: Vec<u8>,
+    jvms: Result<Vec<Jvm>, plist::Error>,
+}
+
+/// `java_home` options that may be passed through, and whether each takes a
+/// value. Anything that changes what it prints, such as `--exec`, would break
+/// parsing its output.
+const JAVA_HOME_ARGS: [(&str, bool); 10] = [
+    ("-v", true),
+    ("--version", true),
+    ("-a", true),
+    ("--arch", true),
+    ("-t", true),
+    ("--task", true),
+    ("-F", false),
+    ("--failfast", false),
+    ("-d64", false),
+    ("-d32", false),
+];
+
+/// Extra arguments for `java_home`, from `--java-home-args` or
+/// `JVMVJ_JAVA_HOME_ARGS`, split on whitespace.
+fn java_home_args(args: &[String], verbosity: Verbosity) -> Vec<String> {
+    let extra = flag_value(args, "--java-home-args")
+        .map(str::to_string)
+        .or_else(|| env::var("JVMVJ_JAVA_HOME_ARGS").ok())
+        .unwrap_or_default();
+    let extra = extra
+        .split_whitespace()
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+
+    let mut words = extra.iter();
+    while let Some(word) = words.next() {
+        let takes_value = JAVA_HOME_ARGS
+            .iter()
+            .find(|(option, _)| option == word)
+            .map(|(_, takes_value)| *takes_value)
+            .unwrap_or_else(|| {
+                exit_with_err(
+                    &format!(
+                        "Unsupported java_home argument {}! Supported arguments are: {}",
+                        word,
+                        JAVA_HOME_ARGS.map(|(option, _)| option).join(", ")
+                    ),
+                    verbosity,
+                )
+            });
+        if takes_value && words.next().is_none() {
+            exit_with_err(
+                &format!("java_home argument {} needs a value!", word),
+                verbosity,
+            );
+        }
+    }
+    extra
+}
+
+/// Where macOS keeps `java_home`.
+const JAVA_HOME: &str = "/usr/libexec/java_home";
+
+/// Runs `java_home -X`, failing with `TimedOut` after `timeout`.
+fn run_java_home(
+    java_home: &Path,
+    timeout: Duration,
+    extra_args: &[String],
+) -> io::Result<JavaHomeOutput> {
+    trace!("running java_home -X {}", extra_args.join(" "));
+    let mut child = Command::new(java_home)
+        .arg("-X")
+        .args(extra_args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().unwrap();
+    let jvms = thread::spawn(move || read_jvms(BufReader::new(stdout)));
+    let stderr = read_in_background(child.stderr.take().unwrap());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    Ok(JavaHomeOutput {
+        status,
+        stderr: stderr.join().unwrap(),
+        jvms: jvms.join().unwrap(),
+    })
+}
+
+#[cfg(not(windows))]
+fn discover_jvms(
+    verbosity: Verbosity,
+    lenient: bool,
+    java_home_args: &[String],
+) -> Vec<Jvm> {
+    java_home_jvms(verbosity, lenient, java_home_args)
+}
+
+#[cfg(windows)]
+fn discover_jvms(
+    _verbosity: Verbosity,
+    _lenient: bool,
+    _java_home_args: &[String],
+) -> Vec<Jvm> {
+    discovery::windows_jvms()
+}
+
+/// The JVMs `java_home` knows about. When `lenient`, a missing or failing
+/// `java_home` just means there are none: in CI mode the runner's
+/// `JAVA_HOME_<N>` variables may still resolve the spec, and with `--scan`
+/// the bundles on disk may still be found.
+#[cfg_attr(windows, allow(dead_code))]
+fn java_home_jvms(
+    verbosity: Verbosity,
+    lenient: bool,
+    java_home_args: &[String],
+) -> Vec<Jvm> {
+    let timeout = java_home_timeout();
+    let output = match run_java_home(Path::new(JAVA_HOME), timeout, java_home_args) {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => exit_with_code(
+            &format!(
+                "java_home did not respond within {:?}; set JVMVJ_TIMEOUT to wait longer",
+                timeout
+            ),
+            verbosity,
+            EXIT_NO_JVMS,
+        ),
+        Err(_) if lenient => return vec![],
+        Err(_) => exit_with_code(
+            "Failed to run java_home. Is this a MacOS system?",
+            verbosity,
+            EXIT_NO_JVMS,
+        ),
+    };
+
+    if !output.status.success() {
+        if lenient {
+            return vec![];
+        }
+        exit_with_code(
+            &format!(
+                "No JDK installed; java_home reported: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            verbosity,
+            EXIT_NO_JVMS,
+        );
+    }
+
+    let jvms = output.jvms.unwrap_or_else(|err| invalid_plist(err));
+    trace!("java_home listed {} JVMs", jvms.len());
+    for jvm in &jvms {
+        trace!(
+            "found {} ({}, {}) at {}",
+            jvm.name,
+            jvm.version,
+            jvm.arch,
+            jvm.home_path
+        );
+    }
+    jvms
+}
+
+/// The JVMs in a `java_home -X` plist piped in with `--plist-stdin`, such as
+/// one captured on another machine.
+fn stdin_jvms(verbosity: Verbosity) -> Vec<Jvm> {
+    let mut plist = Vec::new();
+    if let Err(err) = io::stdin().lock().read_to_end(&mut plist) {
+        exit_with_err(&format!("Failed to read stdin: {}", err), verbosity);
+    }
+    if plist.trim_ascii().is_empty() {
+        exit_with_err("--plist-stdin was given but stdin is empty!", verbosity);
+    }
+    let jvms = read_jvms(&plist[..]).unwrap_or_else(|err| {
+        exit_with_err(
+            &format!("Failed to parse the plist on stdin: {}", err),
+            verbosity,
+        )
+    });
+    trace!("stdin listed {} JVMs", jvms.len());
+    jvms
+}
+
+fn invalid_plist(err: plist::Error) -> ! {
+    exit_with_err(
+        &format!(
+            "Failed to parse the list of JVMs. This should probably be raised as a bug! {}",
+            err
+        ),
+        Verbosity::Normal,
+    )
+}
+
+/// Replaces invalid UTF-8 a line at a time, since the XML parser rejects it
+/// outright and one odd path shouldn't lose the whole list. A newline can't
+/// fall inside a multi-byte character, so each line converts on its own.
+struct LossyLines<R> {
+    inner: R,
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: BufRead> Read for LossyLines<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.line.len() {
+            self.line.clear();
+            self.pos = 0;
+            if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                return Ok(0);
+            }
+            if let Cow::Owned(fixed) = String::from_utf8_lossy(&self.line) {
+                self.line = fixed.into_bytes();
+            }
+        }
+        let len = out.len().min(self.line.len() - self.pos);
+        out[..len].copy_from_slice(&self.line[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// Parses the plist printed by `java_home -X` as it's read.
+fn read_jvms(plist: impl BufRead) -> Result<Vec<Jvm>, plist::Error> {
+    plist::from_reader_xml(LossyLines {
+        inner: plist,
+        line: Vec::new(),
+        pos: 0,
+    })
+}
+
+/// Parses the plist printed by `java_home -X`.
+pub fn parse_jvms(plist: &[u8]) -> Vec<Jvm> {
+    read_jvms(plist).unwrap_or_else(|err| invalid_plist(err))
+}
+
+/// Renders the table printed by a bare `jvmvj`.
+pub fn render_jvm_list(jvms: &[Jvm]) -> String {
+    let options = ListOptions::from_args(&[], &config::Config::default());
+    render_table(&listed(jvms, &options), &options)
+}
+
+/// Resolves a spec as `jvmvj <spec>` would with no flags or config.
+pub fn resolve_spec<'a>(spec: &str, jvms: &'a [Jvm]) -> Option<&'a Jvm> {
+    let options = SelectOptions::from_args(&[], &config::Config::default());
+    resolve(&get_versions_from_input(spec)?, jvms, &options)
+}
+
+/// The user's home directory, or `None` if `HOME` is unset or empty, as in
+/// some containers and CI runners. Features that keep files there are then
+/// skipped rather than using a path relative to the current directory.
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The canonical current directory, which can fail if it was deleted out
+/// from under the shell.
+fn current_dir(verbosity: Verbosity) -> PathBuf {
+    Path::new(".").canonicalize().unwrap_or_else(|err| {
+        exit_with_err(
+            &format!("Couldn't find the path to the current directory: {}", err),
+            verbosity,
+        )
+    })
+}
+
+/// The arguments before `--`, which ends the options so that anything after
+/// it is taken literally.
+fn options(args: &[String]) -> &[String] {
+    let end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    &args[..end]
+}
+
+/// The spec given to a subcommand, which may follow a `--`, as in
+/// `jvmvj use -- --weird-distro-17`.
+fn spec_arg(args: &[String]) -> Option<&String> {
+    match args.get(2) {
+        Some(arg) if arg == "--" => args.get(3),
+        arg => arg,
+    }
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    options(args).iter().any(|arg| arg == flag)
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let args = options(args);
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+pub fn run() {
+    let mut args: Vec<String> = env::args().collect();
+    // Unlike other flags this can come before the subcommand, as in
+    // `java_home -X | jvmvj --plist-stdin list`.
+    let plist_stdin = options(&args)
+        .iter()
+        .position(|arg| arg == "--plist-stdin")
+        .map(|i| args.remove(i))
+        .is_some();
+    let verbosity = Verbosity::from_args(&args);
+    JSON_ERRORS.store(has_flag(&args, "--json"), AtomicOrdering::Relaxed);
+    let _ = INVOCATION_VERBOSITY.set(verbosity);
+    TRACE.store(
+        has_flag(&args, "-vv")
+            && !matches!(verbosity, Verbosity::Quiet | Verbosity::Silent),
+        AtomicOrdering::Relaxed,
+    );
+
+    if has_flag(&args, "--selftest") {
+        selftest();
+    }
+    // These only touch files, so they don't depend on discovering any JVMs,
+    // which fails where java_home does.
+    match args.get(1).map(String::as_str) {
+        Some("migrate") => return migrate(verbosity),
+        Some("prune-cache") => return cache::prune(verbosity),
+        _ => {}
+    }
+
+    let config = config::load(flag_value(&args, "--config"), verbosity);
+    let mut select_options = SelectOptions::from_args(&args, &config);
+    let scan = has_flag(&args, "--scan");
+    let mut jvms = if plist_stdin {
+        stdin_jvms(verbosity)
+    } else {
+        discover_jvms(
+            verbosity,
+            select_options.ci || scan,
+            &java_home_args(&args, verbosity),
+        )
+    };
+    if jvms.is_empty() || scan {
+        discovery::merge(
+            &mut jvms,
+            discovery::scanned_jvms(Path::new(discovery::JAVA_VIRTUAL_MACHINES)),
+        );
+    }
+    if jvms.is_empty() && scan && !select_options.ci {
+        exit_with_code(
+            "No JDK installed; neither java_home nor the scan found any",
+            verbosity,
+            EXIT_NO_JVMS,
+        );
+    }
+    if config.include_homebrew || has_flag(&args, "--include-homebrew") {
+        discovery::merge(
+            &mut jvms,
+            discovery::homebrew_jvms(&discovery::homebrew_prefix()),
+        );
+    }
+    for jvm in &mut jvms {
+        jvm.arch = normalize_arch(&jvm.arch, &config.arch_aliases);
+    }
+
+    match args.get(1) {
+        None => list_all(&jvms, &ListOptions::from_args(&args, &config)),
+        Some(cmd) if cmd == "list" => {
+            list_all(&jvms, &ListOptions::from_args(&args, &config))
+        }
+        Some(cmd) if cmd == "init" => match args.get(2) {
+            Some(shell) if shell != "zsh" && !shell.starts_with('-') => {
+                exit_with_err(
+                    &format!("Unsupported shell {}! Only zsh is supported.", shell),
+                    Verbosity::Normal,
+                )
+            }
+            _ => display_zsh_init(
+                flag_value(&args, "--function-name").unwrap_or("jdk"),
+            ),
+        },
+        Some(cmd) if cmd == "doctor" => doctor(&jvms),
+        Some(cmd) if cmd == "explain" => {
+            let here = current_dir(verbosity);
+            let walk = Walk::from_args(&args, &config);
+            let lookup = find_auto_spec(&here, &args, &config, &walk, verbosity);
+            select_options.arch = lookup.arch.clone();
+            explain(&jvms, lookup, &select_options)
+        }
+        Some(cmd) if cmd == "current" => show_current(
+            &jvms,
+            has_flag(&args, "--json"),
+            has_flag(&args, "--porcelain"),
+            matches!(verbosity, Verbosity::Quiet | Verbosity::Silent),
+        ),
+        Some(cmd) if cmd == "auto" => {
+            let here = current_dir(verbosity);
+            let check = has_flag(&args, "--check");
+            // A JVM chosen from a version file is chosen again without
+            // resolving while nothing it depends on has changed. CI
+            // environments choose their own.
+            let cache_dir = (!check && !select_options.ci)
+                .then(cache::cache_dir)
+                .flatten();
+            let walk = Walk::from_args(&args, &config);
+            let config_path = config::source_path(flag_value(&args, "--config"));
+            let inputs = cache::Inputs {
+                dir: &here,
+                args: &args[1..],
+                config: config_path.as_deref(),
+                env: RESOLUTION_ENV
+                    .iter()
+                    .filter_map(|name| {
+                        Some(format!("{}={}", name, env::var(name).ok()?))
+                    })
+                    .collect(),
+                files: resolution_files(
+                    &here,
+                    &walk,
+                    &args,
+                    &config,
+                    config_path.as_deref(),
+                ),
+                jvms: &jvms,
+            };
+            let cached = cache_dir
+                .as_deref()
+                .and_then(|cache_dir| cache::resolved(cache_dir, &inputs))
+                .and_then(|choice| {
+                    let jvm =
+                        jvms.iter().find(|jvm| jvm.home_path == choice.home)?;
+                    Some((jvm, choice))
+                });
+            if let Some((jvm, choice)) = cached {
+                trace!("using the JVM cached for {}", here.display());
+                select_options.arch = choice.arch;
+                if verbosity == Verbosity::Normal {
+                    warn_if_ambiguous(&choice.spec, &jvms, &select_options);
+                }
+                warn_if_jre(jvm, verbosity);
+                switch(jvm, &select_options, verbosity);
+                return;
+            }
+
+            let lookup = find_auto_spec(&here, &args, &config, &walk, verbosity);
+            select_options.arch = lookup.arch.clone();
+            let version_file = match &lookup.found {
+                Some((SpecSource::VersionFile(path), _)) => Some(path.clone()),
+                _ => None,
+            };
+            let spec = lookup.found.map(|(_, spec)| spec);
+            if check {
+                let matched = spec
+                    .and_then(|spec| get_versions_from_input(&spec))
+                    .and_then(|versions| resolve(&versions, &jvms, &select_options))
+                    .is_some();
+                exit(if matched { 0 } else { 1 })
+            }
+            let spec = spec.unwrap_or_else(|| {
+                no_version_file(&lookup.version_files, verbosity)
+            });
+            if verbosity == Verbosity::Normal {
+                warn_if_ambiguous(&spec, &jvms, &select_options);
+            }
+            let home = switch_to(&spec, &jvms, &select_options, verbosity);
+            // An included file can change without the one including it
+            // changing, so only a file naming its spec directly is cached.
+            // `current+` depends on `JAVA_HOME`, which switching changes.
+            if let (Some(cache_dir), Some(path)) = (cache_dir, version_file) {
+                if spec != "current+"
+                    && !fs::read_to_string(&path)
+                        .is_ok_and(|contents| is_include(&contents))
+                {
+                    let choice = cache::Choice {
+                        spec,
+                        arch: lookup.arch,
+                        home,
+                    };
+                    cache::remember_resolution(&cache_dir, &inputs, choice);
+                }
+            }
+        }
+        Some(cmd) if cmd == "use" => match spec_arg(&args) {
+            Some(spec) => {
+                switch_to(spec, &jvms, &select_options, verbosity);
+                if has_flag(&args, "--remember") {
+                    cache::remember_spec(&current_dir(verbosity), spec, verbosity);
+                }
+            }
+            None => exit_with_err("use requires a version spec!", Verbosity::Normal),
+        },
+        Some(cmd) if cmd == "pin" => match spec_arg(&args) {
+            Some(spec) => pin(
+                spec,
+                &jvms,
+                &select_options,
+                has_flag(&args, "--dry-run"),
+                verbosity,
+            ),
+            None => exit_with_err("pin requires a version spec!", Verbosity::Normal),
+        },
+        Some(cmd) if cmd == "bin" => match spec_arg(&args) {
+            Some(spec) => print_bin(spec, &jvms, &select_options, verbosity),
+            None => exit_with_err("bin requires a version spec!", Verbosity::Normal),
+        },
+        Some(cmd) if cmd == "info" => match spec_arg(&args) {
+            Some(spec) => print_info(spec, &jvms, &select_options, verbosity),
+            None => {
+                exit_with_err("info requires a version spec!", Verbosity::Normal)
+            }
+        },
+        Some(cmd) if cmd == "resolve" => match spec_arg(&args) {
+            Some(spec) => {
+                println!(
+                    "{}",
+                    select(spec, &jvms, &select_options, verbosity).version
+                )
+            }
+            None => {
+                exit_with_err("resolve requires a version spec!", Verbosity::Normal)
+            }
+        },
+        Some(cmd) if cmd == "resolve-many" => {
+            let specs = args
+                .iter()
+                .enumerate()
+                .skip(2)
+                .filter(|(i, arg)| {
+                    !arg.starts_with('-')
+                        && !matches!(
+                            args[i - 1].as_str(),
+                            "--config" | "--select-index" | "--arch"
+                        )
+                })
+                .map(|(_, arg)| arg)
+                .collect::<Vec<&String>>();
+            if specs.is_empty() {
+                exit_with_err(
+                    "resolve-many requires at least one version spec!",
+                    Verbosity::Normal,
+                )
+            }
+            resolve_many(
+                &specs,
+                &jvms,
+                &select_options,
+                has_flag(&args, "--fail-fast"),
+                verbosity,
+            )
+        }
+        Some(cmd) if cmd == "exec" => {
+            let separator = args.iter().position(|arg| arg == "--");
+            match (args.get(2), separator) {
+                (Some(spec), Some(separator))
+                    if separator > 2 && separator + 1 < args.len() =>
+                {
+                    let options =
+                        SelectOptions::from_args(&args[..separator], &config);
+                    exec_with(spec, &args[separator + 1..], &jvms, &options)
+                }
+                _ => exit_with_err(
+                    "Usage: jvmvj exec <spec> -- <command>...",
+                    Verbosity::Normal,
+                ),
+            }
+        }
+        Some(cmd) if cmd == "--version-regex" => match args.get(2) {
+            Some(pattern) => switch_to_version_regex(pattern, &jvms, verbosity),
+            None => exit_with_err(
+                "--version-regex requires a pattern!",
+                Verbosity::Normal,
+            ),
+        },
+        Some(cmd) if cmd == "--dir-name" => match args.get(2) {
+            Some(dir_name) => switch_to_dir_name(dir_name, &jvms),
+            None => exit_with_err(
+                "--dir-name requires a directory name!",
+                Verbosity::Normal,
+            ),
+        },
+        Some(cmd) if cmd == "--" => match args.get(2) {
+            Some(spec) => {
+                switch_to(spec, &jvms, &select_options, verbosity);
+            }
+            None => list_all(&jvms, &ListOptions::from_args(&args, &config)),
+        },
+        Some(spec) => {
+            switch_to(spec, &jvms, &select_options, verbosity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jvm(version: &str, name: &str, bundle_id: &str, arch: &str) -> Jvm {
+        Jvm {
+            arch: arch.to_string(),
+            bundle_id: bundle_id.to_string(),
+            enabled: true,
+            home_path: format!("/jvms/{}/Contents/Home", bundle_id),
+            name: name.to_string(),
+            platform_version: version.to_string(),
+            vendor: name.split_whitespace().next().unwrap_or("").to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    /// An empty directory for one test, under the system temp directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "jvmvj-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn compares_versions() {
+        let table = [
+            ("1.8.0_302", "1.8.0_292", Ordering::Greater),
+            ("17.0.10", "17.0.9", Ordering::Greater),
+            ("17.0.9", "17.0.10", Ordering::Less),
+            ("21", "21.0.0", Ordering::Less),
+            ("11.0.2", "11.0.2", Ordering::Equal),
+            ("17.0.9+11", "17.0.9+9", Ordering::Greater),
+            ("1.8.0_302", "11.0.1", Ordering::Less),
+            ("21.0.1-ea", "21.0.1", Ordering::Less),
+            ("21.0.1", "21.0.1-ea", Ordering::Greater),
+            ("21.0.1-ea", "21.0.0", Ordering::Greater),
+            ("21.0.1-ea", "21.0.1+9", Ordering::Less),
+            ("17.0.9.1", "17.0.9", Ordering::Greater),
+        ];
+        for (a, b, expected) in VERSION_VECTORS.into_iter().chain(table) {
+            assert_eq!(compare_versions(a, b), expected, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn parses_specs() {
+        for (spec, expected) in SPEC_VECTORS {
+            let parsed = get_version_from_input(spec)
+                .map(|v| (v.number, v.distro.map(|distro| distro.to_lowercase())));
+            let expected = expected
+                .map(|(number, distro)| (number, distro.map(str::to_string)));
+            assert_eq!(parsed, expected, "spec {:?}", spec);
+        }
+    }
+
+    #[test]
+    fn parses_spec_qualifiers() {
+        let v = get_version_from_input("21-ea").unwrap();
+        assert!(v.early_access);
+        let v = get_version_from_input("temurin-17@aarch64").unwrap();
+        assert_eq!(v.arch.as_deref(), Some("aarch64"));
+        assert_eq!(get_versions_from_input("17 11").map(|v| v.len()), Some(2));
+    }
+
+    #[test]
+    fn applies_the_spec_prefix_only_without_a_distro() {
+        let jvms = [
+            jvm("17.0.13", "Zulu 17", "com.azul.zulu.17.jdk", "x86_64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "x86_64"),
+        ];
+        let options = SelectOptions::from_args(&[], &config::Config::default());
+        let selected = |spec| {
+            let v = with_spec_prefix(
+                get_version_from_input(spec).unwrap(),
+                Some("temurin-"),
+            );
+            find_jvm(&v, &jvms, &options).map(|jvm| jvm.bundle_id.as_str())
+        };
+        assert_eq!(selected("17"), Some("net.temurin.17.jdk"));
+        assert_eq!(selected("17.0.13-zulu"), Some("com.azul.zulu.17.jdk"));
+        assert_eq!(selected("17-zulu"), Some("com.azul.zulu.17.jdk"));
+        assert_eq!(selected("zulu-17"), Some("com.azul.zulu.17.jdk"));
+    }
+
+    #[test]
+    fn keeps_the_distro_of_a_dash_leading_spec() {
+        let jvms = [
+            jvm("17.0.2", "Zulu 17", "com.azul.zulu.17.jdk", "x86_64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "x86_64"),
+        ];
+        let options = SelectOptions::from_args(&[], &config::Config::default());
+        let selected = |spec| {
+            let v = get_version_from_input(spec).unwrap();
+            find_jvm(&v, &jvms, &options).map(|jvm| jvm.bundle_id.as_str())
+        };
+        assert_eq!(selected("-zulu-17"), Some("com.azul.zulu.17.jdk"));
+        assert_eq!(selected("--weird-distro-17"), None);
+
+        let args = ["jvmvj", "use", "--", "--weird-distro-17"].map(String::from);
+        assert_eq!(
+            spec_arg(&args).map(String::as_str),
+            Some("--weird-distro-17")
+        );
+        assert!(!has_flag(&args, "--weird-distro-17"));
+    }
+
+    #[test]
+    fn parses_version_components() {
+        let components = |version| {
+            parse_version_components(version)
+                .map(|c| (c.feature, c.interim, c.update, c.patch))
+        };
+        assert_eq!(components("17.0.9+11"), Some((17, 0, 9, 0)));
+        assert_eq!(components("21"), Some((21, 0, 0, 0)));
+        assert_eq!(components("11.0.20.1"), Some((11, 0, 20, 1)));
+        assert_eq!(components("1.8.0_302"), Some((8, 0, 302, 0)));
+        assert_eq!(components("21-ea"), Some((21, 0, 0, 0)));
+        assert_eq!(components("seventeen"), None);
+    }
+
+    #[test]
+    fn parses_major_versions() {
+        assert_eq!(parse_major_version("17.0.9"), Ok(17));
+        assert_eq!(parse_major_version("1.8.0_302"), Ok(8));
+        assert!(parse_major_version("21").is_err());
+        let mut jvm = jvm("", "Odd 21", "odd.21", "arm64");
+        jvm.platform_version = "21.0.1".to_string();
+        assert_eq!(jvm.major_version(), 21);
+    }
+
+    #[test]
+    fn trims_bundle_ids() {
+        assert_eq!(trim_bundle_id("net.adoptium.17.jdk"), "adoptium");
+        assert_eq!(trim_bundle_id("com.azul.zulu.17.jdk"), "azul");
+        assert_eq!(trim_bundle_id("homebrew.openjdk@17"), "homebrew.openjdk@17");
+    }
+
+    #[test]
+    fn shortens_list_fields() {
+        let jvm = jvm("17.0.2", "Zulu 17", "com.azul.zulu.17.jdk", "arm64");
+        let config = config::Config::default();
+        let args = ["jvmvj".to_string(), "list".to_string()];
+        let options = ListOptions::from_args(&args, &config);
+        assert_eq!(jvm.field("bundle_id", &options), "com.azul.zulu.17.jdk");
+        let args = [&args[..], &["--trim-bundle".to_string()]].concat();
+        let options = ListOptions::from_args(&args, &config);
+        assert_eq!(jvm.field("bundle_id", &options), "azul");
+    }
+
+    #[test]
+    fn filters_list_by_enabled() {
+        let args = |flags: &[&str]| {
+            ["jvmvj", "list"]
+                .iter()
+                .chain(flags)
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(enabled_filter(&args(&[])), Some(true));
+        assert_eq!(enabled_filter(&args(&["--enabled"])), Some(true));
+        assert_eq!(enabled_filter(&args(&["--disabled"])), Some(false));
+        assert_eq!(enabled_filter(&args(&["--include-disabled"])), None);
+    }
+
+    #[test]
+    fn matches_distros() {
+        let temurin =
+            jvm("17.0.9", "OpenJDK 17.0.9", "net.temurin.17.jdk", "x86_64");
+        let zulu = jvm("17.0.2", "Zulu 17", "com.azul.zulu.17.jdk", "arm64");
+        assert!(is_distro(&temurin, "temurin"));
+        assert!(is_distro(&temurin, "tem"));
+        assert!(!is_distro(&temurin, "urin"));
+        assert!(is_distro(&zulu, "zulu"));
+        assert!(is_distro(&temurin, "temurin*17"));
+        assert!(!is_distro(&zulu, "temurin*"));
+        assert!(glob_matches("com.oracle.graalvm21.jdk", "graal*"));
+        assert!(!glob_matches("net.temurin.21.jdk", "temurin*17"));
+    }
+
+    #[test]
+    fn matches_distros_only_at_word_starts() {
+        let graal = jvm(
+            "17.0.9",
+            "GraalVM CE 17",
+            "com.oracle.graalvm17.jdk",
+            "x86_64",
+        );
+        let zulu = jvm("17.0.2", "Zulu 17", "com.azul.zulu.17.jdk", "x86_64");
+        let matches = |spec, jvm: &Jvm| {
+            let v = get_version_from_input(spec).unwrap();
+            v.number == Some(jvm.major_version()) && distro_matches(&v, jvm)
+        };
+        assert!(!matches("z17", &graal));
+        assert!(matches("zulu17", &zulu));
+        assert!(!matches("zulu17", &graal));
+        assert!(matches("graal17", &graal));
+    }
+
+    #[test]
+    fn filters_with_where_expressions() {
+        let jvms = [
+            jvm("21.0.1", "Temurin 21", "net.temurin.21.jdk", "arm64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "x86_64"),
+            jvm("11.0.2", "Zulu 11", "com.azul.zulu.11.jdk", "arm64"),
+        ];
+        let matching = |expression| {
+            let filter =
+                filter::Filter::parse(expression, &BTreeMap::new()).unwrap();
+            jvms.iter()
+                .filter(|jvm| filter.matches(jvm))
+                .map(|jvm| jvm.version.as_str())
+                .collect::<Vec<&str>>()
+        };
+        assert_eq!(matching("major>=17 and arch==aarch64"), ["21.0.1"]);
+        assert_eq!(
+            matching("major == 11 or vendor==temurin"),
+            ["21.0.1", "17.0.9", "11.0.2"]
+        );
+        assert_eq!(matching("vendor=='Zulu'"), ["11.0.2"]);
+        assert_eq!(matching("enabled==false"), Vec::<&str>::new());
+
+        for invalid in [
+            "major>=x",
+            "arch>=arm64",
+            "foo==1",
+            "major>=",
+            "major==17 xor",
+            "vendor=='a",
+        ] {
+            assert!(
+                filter::Filter::parse(invalid, &BTreeMap::new()).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn checks_compat_constraints() {
+        let jvms = [
+            jvm("21.0.1", "Temurin 21", "net.temurin.21.jdk", "arm64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "arm64"),
+            jvm("11.0.2", "Zulu 11", "com.azul.zulu.11.jdk", "arm64"),
+        ];
+        let allowed = |constraints| {
+            let constraints = Constraint::parse_all(constraints);
+            jvms.iter()
+                .filter(|jvm| constraints.iter().all(|c| c.allows(jvm)))
+                .map(|jvm| jvm.major_version())
+                .collect::<Vec<u16>>()
+        };
+        assert_eq!(allowed(">=17"), [21, 17]);
+        assert_eq!(allowed(">=11 <21"), [17, 11]);
+        assert_eq!(allowed("17"), [17]);
+        assert_eq!(allowed(">17.0.5"), [21, 17]);
+    }
+
+    fn selected(spec: &str, jvms: &[Jvm], config: &config::Config) -> String {
+        let options = SelectOptions::from_args(&[], config);
+        let versions = get_versions_from_input(spec).unwrap();
+        resolve(&versions, jvms, &options)
+            .unwrap()
+            .bundle_id
+            .clone()
+    }
+
+    #[test]
+    fn orders_candidates() {
+        let jvms = [
+            jvm("17.0.2", "Zulu 17", "com.azul.zulu.17.jdk", "arm64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "arm64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.x64.jdk", "x86_64"),
+            jvm("17.0.10-ea", "Temurin 17", "net.temurin.17ea.jdk", "x86_64"),
+            jvm(
+                "17.0.2",
+                "Corretto 17",
+                "com.amazon.corretto.17.jdk",
+                "x86_64",
+            ),
+        ];
+        let mut options = SelectOptions::from_args(&[], &config::Config::default());
+        options.native_arch = Some("x86_64".to_string());
+        let order = |options: &SelectOptions| {
+            candidates(&get_version_from_input("17").unwrap(), &jvms, options)
+                .iter()
+                .map(|jvm| jvm.bundle_id.as_str())
+                .collect::<Vec<&str>>()
+        };
+        assert_eq!(
+            order(&options),
+            [
+                "net.temurin.17.x64.jdk",
+                "net.temurin.17.jdk",
+                "com.amazon.corretto.17.jdk",
+                "com.azul.zulu.17.jdk",
+                "net.temurin.17ea.jdk",
+            ]
+        );
+        options.distro_priority = vec!["zulu".to_string()];
+        assert_eq!(order(&options)[0], "com.azul.zulu.17.jdk");
+        options.default_distro =
+            BTreeMap::from([("17".to_string(), "corretto".to_string())]);
+        assert_eq!(
+            order(&options)[..2],
+            ["com.amazon.corretto.17.jdk", "com.azul.zulu.17.jdk"]
+        );
+    }
+
+    #[test]
+    fn prefers_the_default_distro_for_each_major() {
+        let jvms = [
+            jvm("1.8.0_302", "Zulu 8", "com.azul.zulu.8.jdk", "x86_64"),
+            jvm("1.8.0_392", "Temurin 8", "net.temurin.8.jdk", "x86_64"),
+            jvm("17.0.10", "Zulu 17", "com.azul.zulu.17.jdk", "x86_64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "x86_64"),
+        ];
+        let config = config::Config {
+            default_distro: BTreeMap::from([
+                ("8".to_string(), "zulu".to_string()),
+                ("17".to_string(), "temurin".to_string()),
+            ]),
+            ..config::Config::default()
+        };
+        assert_eq!(selected("8", &jvms, &config), "com.azul.zulu.8.jdk");
+        assert_eq!(selected("17", &jvms, &config), "net.temurin.17.jdk");
+        assert_eq!(selected("zulu-17", &jvms, &config), "com.azul.zulu.17.jdk");
+        assert_eq!(
+            selected("17", &jvms, &config::Config::default()),
+            "com.azul.zulu.17.jdk"
+        );
+    }
+
+    #[test]
+    fn prefers_the_newest_distro_when_asked() {
+        let jvms = [
+            jvm("17.0.2", "Zulu 17", "com.azul.zulu.17.jdk", "x86_64"),
+            jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "x86_64"),
+        ];
+        let config = config::Config {
+            distro_priority: vec!["zulu".to_string()],
+            ..config::Config::default()
+        };
+        let args = ["jvmvj".to_string(), "--prefer-newest-distro".to_string()];
+        let options = SelectOptions::from_args(&args, &config);
+        let versions = get_versions_from_input("17").unwrap();
+        let newest = resolve(&versions, &jvms, &options).unwrap();
+        assert_eq!(newest.version, "17.0.9");
+        assert_eq!(selected("17", &jvms, &config), "com.azul.zulu.17.jdk");
+    }
+
+    #[test]
+    fn truncates_with_an_ellipsis() {
+        assert_eq!(truncate_with_ellipsis("OpenJDK 17", 20), "OpenJDK 17");
+        assert_eq!(truncate_with_ellipsis("OpenJDK 17", 5), "Open…");
+        assert_eq!(truncate_with_ellipsis("OpenJDK 17", 10), "OpenJDK 17");
+    }
+
+    #[test]
+    fn checks_shell_identifiers_and_quotes() {
+        assert!(is_shell_identifier("jdk"));
+        assert!(is_shell_identifier("_use_java2"));
+        assert!(!is_shell_identifier("2jdk"));
+        assert!(!is_shell_identifier("use-java"));
+        assert!(!is_shell_identifier(""));
+        assert_eq!(shell_quote("/a b/it's"), "'/a b/it'\\''s'");
+    }
+
+    #[test]
+    fn reads_asdf_and_sdkman_files() {
+        let dir = temp_dir("tool-files");
+        fs::write(
+            dir.join(".tool-versions"),
+            "nodejs 20\njava temurin-17.0.9+9 zulu-17\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join(".sdkmanrc"),
+            "# managed by sdkman\nmaven=3.9.6\njava = 21.0.1-tem # LTS\n",
+        )
+        .unwrap();
+        assert_eq!(
+            find_version_string_from_tool_versions(&dir.join(".tool-versions"))
+                .as_deref(),
+            Some("temurin-17.0.9+9 zulu-17")
+        );
+        assert_eq!(
+            find_version_string_from_sdkmanrc(&dir.join(".sdkmanrc")).as_deref(),
+            Some("21.0.1-tem")
+        );
+        assert_eq!(from_sdkman("21.0.1-tem"), "temurin-21");
+    }
+
+    #[test]
+    fn extracts_versions_from_build_files() {
+        let dir = temp_dir("build-files");
+        assert_eq!(heuristics::gradle_version(&dir), None);
+        fs::write(
+            dir.join("build.gradle.kts"),
+            "java {\n    toolchain {\n        languageVersion = JavaLanguageVersion.of(17)\n    }\n}\n",
+        )
+        .unwrap();
+        assert_eq!(heuristics::gradle_version(&dir).as_deref(), Some("17"));
+
+        fs::write(
+            dir.join("pom.xml"),
+            "<project><properties><maven.compiler.release>17</maven.compiler.release></properties></project>",
+        )
+        .unwrap();
+        assert_eq!(heuristics::maven_version(&dir).as_deref(), Some("17"));
+        fs::write(dir.join("pom.xml"), "<java.version>1.8</java.version>").unwrap();
+        assert_eq!(heuristics::maven_version(&dir).as_deref(), Some("8"));
+        fs::write(dir.join("pom.xml"), "<java.version>${jdk}</java.version>")
+            .unwrap();
+        assert_eq!(heuristics::maven_version(&dir), None);
+
+        fs::write(
+            dir.join("Dockerfile"),
+            "# build\nFROM eclipse-temurin:17-jdk AS build\n",
+        )
+        .unwrap();
+        assert_eq!(
+            heuristics::dockerfile_version(&dir).as_deref(),
+            Some("temurin-17")
+        );
+        fs::write(
+            dir.join("Dockerfile"),
+            "FROM --platform=linux/amd64 openjdk:21\n",
+        )
+        .unwrap();
+        assert_eq!(heuristics::dockerfile_version(&dir).as_deref(), Some("21"));
+        fs::write(dir.join("Dockerfile"), "FROM maven:3.9\n").unwrap();
+        assert_eq!(heuristics::dockerfile_version(&dir), None);
+    }
+
+    #[test]
+    fn walks_up_to_boundary_markers() {
+        let root = temp_dir("walk");
+        let project = root.join("project");
+        let sub = project.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(root.join(".java-version"), "11\n").unwrap();
+        let files = [".java-version".to_string()];
+        let find = |walk: &Walk| {
+            find_upwards(&sub, walk, &|dir| {
+                find_version_file_in_dir(dir, &files, Verbosity::Normal)
+            })
+            .map(|(_, spec)| spec)
+        };
+
+        assert_eq!(find(&Walk::ToRoot).as_deref(), Some("11"));
+        assert_eq!(find(&Walk::Here), None);
+        fs::write(project.join(".jvmvj-root"), "").unwrap();
+        let config = config::Config {
+            boundary_markers: vec![".jvmvj-root".to_string()],
+            ..config::Config::default()
+        };
+        let walk = Walk::from_args(&[], &config);
+        assert_eq!(find(&walk), None);
+        fs::write(project.join(".java-version"), "17\n").unwrap();
+        assert_eq!(find(&walk).as_deref(), Some("17"));
+    }
+
+    #[test]
+    fn follows_includes() {
+        let root = temp_dir("include");
+        fs::create_dir_all(root.join("shared")).unwrap();
+        fs::create_dir_all(root.join("project")).unwrap();
+        fs::write(root.join("shared/.java-version"), "temurin-21\n").unwrap();
+        fs::write(
+            root.join("project/.java-version"),
+            "include ../shared/.java-version\n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_version_file(
+                &root.join("project/.java-version"),
+                Verbosity::Normal
+            ),
+            "temurin-21"
+        );
+        assert!(is_include("include ../shared/.java-version"));
+        assert!(!is_include("17"));
+    }
+
+    #[test]
+    fn layers_rc_files() {
+        let root = temp_dir("rc");
+        let project = root.join("project");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(
+            root.join(".jvmvjrc"),
+            "arch = \"x86_64\"\nversion = \"11\"\n",
+        )
+        .unwrap();
+        fs::write(project.join(".jvmvjrc"), "version = \"17\"\n").unwrap();
+
+        let rc = rc::load(&project, &Walk::ToRoot, Verbosity::Normal);
+        assert_eq!(rc.version.as_deref(), Some("17"));
+        assert_eq!(rc.arch.as_deref(), Some("x86_64"));
+        let rc = rc::load(&project, &Walk::Here, Verbosity::Normal);
+        assert_eq!(rc.arch, None);
+    }
+
+    fn cache_inputs<'a>(
+        dir: &'a Path,
+        args: &'a [String],
+        config_path: &'a Path,
+        env: &[&str],
+        jvms: &'a [Jvm],
+    ) -> cache::Inputs<'a> {
+        let config = config::Config::default();
+        cache::Inputs {
+            dir,
+            args,
+            config: Some(config_path),
+            env: env.iter().map(|var| var.to_string()).collect(),
+            files: resolution_files(
+                dir,
+                &Walk::ToRoot,
+                args,
+                &config,
+                Some(config_path),
+            ),
+            jvms,
+        }
+    }
+
+    #[test]
+    fn reuses_resolutions_until_an_input_changes() {
+        let root = temp_dir("cache");
+        let sub = root.join("project/sub");
+        let cache_dir = root.join("cache");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(root.join("project/.java-version"), "17\n").unwrap();
+        let config_path = root.join("config.toml");
+        let args = ["auto".to_string()];
+        let mut jvms =
+            vec![jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "arm64")];
+        let resolved = |jvms: &[Jvm], env: &[&str]| {
+            let inputs = cache_inputs(&sub, &args, &config_path, env, jvms);
+            cache::resolved(&cache_dir, &inputs).map(|choice| choice.home)
+        };
+        let remember = |jvms: &[Jvm]| {
+            let inputs = cache_inputs(&sub, &args, &config_path, &[], jvms);
+            let choice = cache::Choice {
+                spec: "17".to_string(),
+                arch: None,
+                home: "/jvms/17".to_string(),
+            };
+            cache::remember_resolution(&cache_dir, &inputs, choice)
+        };
+
+        assert_eq!(resolved(&jvms, &[]), None);
+        remember(&jvms);
+        assert_eq!(resolved(&jvms, &[]).as_deref(), Some("/jvms/17"));
+        assert_eq!(resolved(&jvms, &["JVMVJ_SPEC_PREFIX=zulu-"]), None);
+
+        fs::write(sub.join(".java-version"), "21\n").unwrap();
+        assert_eq!(resolved(&jvms, &[]), None);
+        fs::remove_file(sub.join(".java-version")).unwrap();
+        assert_eq!(resolved(&jvms, &[]).as_deref(), Some("/jvms/17"));
+
+        fs::write(sub.join(".jvmvjrc"), "arch = \"arm64\"\n").unwrap();
+        assert_eq!(resolved(&jvms, &[]), None);
+        fs::remove_file(sub.join(".jvmvjrc")).unwrap();
+
+        fs::write(&config_path, "distro_priority = [\"zulu\"]\n").unwrap();
+        assert_eq!(resolved(&jvms, &[]), None);
+        remember(&jvms);
+        assert_eq!(resolved(&jvms, &[]).as_deref(), Some("/jvms/17"));
+
+        jvms.push(jvm("17.0.10", "Zulu 17", "com.azul.zulu.17.jdk", "arm64"));
+        assert_eq!(resolved(&jvms, &[]), None);
+    }
+
+    #[test]
+    fn lets_quiet_and_silent_win() {
+        let verbosity = |args: &[&str]| {
+            let args = args
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>();
+            Verbosity::from_args(&args)
+        };
+        assert!(
+            verbosity(&["jvmvj", "auto", "--quiet", "--human"]) == Verbosity::Quiet
+        );
+        assert!(verbosity(&["jvmvj", "auto", "--human", "-q"]) == Verbosity::Quiet);
+        assert!(
+            verbosity(&["jvmvj", "auto", "--human", "--silent"])
+                == Verbosity::Silent
+        );
+        assert!(verbosity(&["jvmvj", "auto", "--human"]) == Verbosity::Human);
+        assert!(verbosity(&["jvmvj", "use", "--", "-q"]) == Verbosity::Normal);
+    }
+
+    /// Makes a JDK home at `home` whose `release` file gives `version`.
+    fn fake_home(home: &Path, version: &str, vendor: &str) {
+        fs::create_dir_all(home.join("bin")).unwrap();
+        fs::write(home.join("bin/java"), "").unwrap();
+        fs::write(
+            home.join("release"),
+            format!(
+                "JAVA_VERSION=\"{}\"\nIMPLEMENTOR=\"{}\"\nOS_ARCH=\"aarch64\"\n",
+                version, vendor
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn scans_java_virtual_machines() {
+        let root = temp_dir("scan");
+        fake_home(
+            &root.join("temurin-17.jdk/Contents/Home"),
+            "17.0.9",
+            "Eclipse Adoptium",
+        );
+        fs::write(
+            root.join("temurin-17.jdk/Contents/Info.plist"),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\"><dict><key>CFBundleIdentifier</key><string>net.temurin.17.jdk</string></dict></plist>\n",
+        )
+        .unwrap();
+        fake_home(
+            &root.join("zulu-21.jdk/Contents/Home"),
+            "21.0.1",
+            "Azul Systems, Inc.",
+        );
+        fs::create_dir_all(root.join("broken.jdk/Contents/Home")).unwrap();
+
+        let mut jvms = discovery::scanned_jvms(&root);
+        jvms.sort_by(|a, b| a.version.cmp(&b.version));
+        let found = jvms
+            .iter()
+            .map(|jvm| {
+                (
+                    jvm.version.as_str(),
+                    jvm.bundle_id.as_str(),
+                    jvm.vendor.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("17.0.9", "net.temurin.17.jdk", "Eclipse Adoptium"),
+                ("21.0.1", "zulu-21.jdk", "Azul Systems, Inc."),
+            ]
+        );
+        assert_eq!(
+            jvms[0].home_path,
+            root.join("temurin-17.jdk/Contents/Home").to_string_lossy()
+        );
+
+        let mut listed =
+            vec![jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "arm64")];
+        listed[0].home_path = jvms[0].home_path.clone();
+        discovery::merge(&mut listed, jvms);
+        assert_eq!(listed.len(), 2);
+        assert!(discovery::scanned_jvms(&root.join("missing")).is_empty());
+    }
+
+    #[test]
+    fn finds_homebrew_openjdk_formulae() {
+        let prefix = temp_dir("homebrew");
+        let home = |formula: &str| {
+            prefix
+                .join("opt")
+                .join(formula)
+                .join("libexec/openjdk.jdk/Contents/Home")
+        };
+        fake_home(&home("openjdk@17"), "17.0.9", "Homebrew");
+        fake_home(&home("openjdk"), "21.0.1", "Homebrew");
+        fake_home(&home("maven"), "3.9.6", "Apache");
+
+        let mut jvms = discovery::homebrew_jvms(&prefix);
+        jvms.sort_by(|a, b| a.version.cmp(&b.version));
+        let found = jvms
+            .iter()
+            .map(|jvm| (jvm.version.as_str(), jvm.bundle_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("17.0.9", "homebrew.openjdk@17"),
+                ("21.0.1", "homebrew.openjdk")
+            ]
+        );
+        assert_eq!(jvms[0].home_path, home("openjdk@17").to_string_lossy());
+
+        let mut listed =
+            vec![jvm("17.0.9", "OpenJDK 17", "homebrew.openjdk@17", "arm64")];
+        listed[0].home_path = jvms[0].home_path.clone();
+        discovery::merge(&mut listed, jvms);
+        assert_eq!(listed.len(), 2);
+    }
+
+    #[test]
+    fn normalizes_arches() {
+        let aliases = BTreeMap::from([("amd".to_string(), "x86_64".to_string())]);
+        assert_eq!(normalize_arch("aarch64", &aliases), "arm64");
+        assert_eq!(normalize_arch("AMD64", &aliases), "x86_64");
+        assert_eq!(normalize_arch("amd", &aliases), "x86_64");
+        assert_eq!(normalize_arch("riscv64", &aliases), "riscv64");
+    }
+
+    /// Writes an executable shell script at `dir/name` running `body`.
+    #[cfg(unix)]
+    fn stub_script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_a_failing_java_home() {
+        let java_home = stub_script(
+            &temp_dir("failing-java-home"),
+            "java_home",
+            "echo 'No Java runtime present, requesting install.' >&2; exit 1",
+        );
+        let output = run_java_home(&java_home, Duration::from_secs(5), &[]).unwrap();
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr).trim(),
+            "No Java runtime present, requesting install."
+        );
+    }
+
+    #[test]
+    fn shortens_arch_labels_only_in_the_table() {
+        assert_eq!(short_arch_label("x86_64"), "x64");
+        assert_eq!(short_arch_label("arm64"), "arm");
+        assert_eq!(short_arch_label("riscv64"), "riscv64");
+
+        let jvm = jvm("17.0.9", "Temurin 17", "net.temurin.17.jdk", "x86_64");
+        let config = config::Config::default();
+        let args = ["jvmvj", "list", "--short-arch"].map(String::from);
+        let options = ListOptions::from_args(&args, &config);
+        assert_eq!(jvm.field("arch", &options), "x64");
+        assert_eq!(json_jvm(&jvm, &options)["arch"], "x86_64");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_waiting_for_a_hung_java_home() {
+        let java_home =
+            stub_script(&temp_dir("hung-java-home"), "java_home", "exec sleep 30");
+        let started = Instant::now();
+        let err = run_java_home(&java_home, Duration::from_millis(200), &[])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn matches_graalvm_by_any_of_its_identifiers() {
+        let temurin =
+            jvm("21.0.1", "OpenJDK 21.0.1", "net.temurin.21.jdk", "x86_64");
+        let graals = [
+            jvm("21.0.1", "GraalVM CE 21", "org.graalvm.21.jdk", "x86_64"),
+            jvm(
+                "21.0.1",
+                "Oracle GraalVM 21",
+                "com.oracle.graalvm21.jdk",
+                "x86_64",
+            ),
+            jvm("21.0.1", "Oracle 21", "com.oracle.graalvm.jdk", "x86_64"),
+        ];
+        let config = config::Config::default();
+        for graal in graals {
+            let bundle_id = graal.bundle_id.clone();
+            let jvms = [temurin.clone(), graal];
+            for spec in ["graal21", "graalvm-21"] {
+                assert_eq!(selected(spec, &jvms, &config), bundle_id, "{}", spec);
+            }
+        }
+    }
+
+    #[test]
+    fn lists_non_ascii_and_invalid_utf8_paths() {
+        let plist = |home: &[u8]| {
+            [
+                &b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\">\n<array>\n<dict>\n<key>JVMArch</key><string>arm64</string>\n<key>JVMBundleID</key><string>com.azul.zulu.17.jdk</string>\n<key>JVMEnabled</key><true/>\n<key>JVMHomePath</key><string>"[..],
+                home,
+                b"</string>\n<key>JVMName</key><string>Zulu 17</string>\n<key>JVMPlatformVersion</key><string>17.0.2</string>\n<key>JVMVendor</key><string>Azul Systems, Inc.</string>\n<key>JVMVersion</key><string>17.0.2</string>\n</dict>\n</array>\n</plist>\n",
+            ]
+            .concat()
+        };
+
+        let home =
+            "/Users/zoë/Library/Java/JavaVirtualMachines/zulu-17.jdk/Contents/Home";
+        let jvms = parse_jvms(&plist(home.as_bytes()));
+        assert_eq!(jvms[0].home_path, home);
+        let args = ["jvmvj", "list", "--fields", "name,home"].map(String::from);
+        let options = ListOptions::from_args(&args, &config::Config::default());
+        assert!(render_table(&listed(&jvms, &options), &options).contains(home));
+
+        let jvms = parse_jvms(&plist(b"/Users/zo\xebl/zulu-17.jdk/Contents/Home"));
+        assert_eq!(
+            jvms[0].home_path,
+            "/Users/zo\u{fffd}l/zulu-17.jdk/Contents/Home"
+        );
+        assert_eq!(jvms[0].version, "17.0.2");
+    }
+
+    #[test]
+    fn streams_the_plist_like_parsing_it_whole() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("benches/fixtures/java_home.plist");
+        let streamed =
+            read_jvms(BufReader::new(fs::File::open(&path).unwrap())).unwrap();
+        let whole =
+            plist::from_bytes::<Vec<Jvm>>(&fs::read(&path).unwrap()).unwrap();
+        assert!(!whole.is_empty());
+        assert_eq!(
+            serde_json::to_value(&streamed).unwrap(),
+            serde_json::to_value(&whole).unwrap()
+        );
+    }
+
+    #[test]
+    fn selects_the_newest_of_a_distro_without_a_version() {
+        let jvms = [
+            jvm("17.0.2", "Zulu 17", "com.azul.zulu.17.jdk", "x86_64"),
+            jvm("21.0.5", "OpenJDK 21.0.5", "net.temurin.21.jdk", "x86_64"),
+            jvm("21.0.1", "Zulu 21", "com.azul.zulu.21.jdk", "x86_64"),
+            jvm("11.0.21", "Zulu 11", "com.azul.zulu.11.jdk", "x86_64"),
+        ];
+        let config = config::Config::default();
+        assert_eq!(selected("zulu", &jvms, &config), "com.azul.zulu.21.jdk");
+        assert_eq!(selected("temurin", &jvms, &config), "net.temurin.21.jdk");
+    }
+
+    #[test]
+    fn treats_every_arch_alike_with_arch_all() {
+        let jvms = [
+            jvm("17.0.9", "Zulu 17", "com.azul.zulu.17.jdk", "arm64"),
+            jvm("17.0.9", "OpenJDK 17.0.9", "net.temurin.17.jdk", "x86_64"),
+        ];
+        let config = config::Config::default();
+        let v = get_version_from_input("17").unwrap();
+        let pick = |options: &SelectOptions| find_jvm(&v, &jvms, options).unwrap();
+
+        // As on Apple Silicon, where the native arm64 build wins a tie.
+        let mut options = SelectOptions::from_args(&[], &config);
+        options.native_arch = Some("arm64".to_string());
+        assert_eq!(pick(&options).arch, "arm64");
+
+        let args = ["jvmvj", "use", "17", "--arch", "all"].map(String::from);
+        let options = SelectOptions::from_args(&args, &config);
+        assert_eq!(options.native_arch, None);
+        assert_eq!(pick(&options).arch, "x86_64");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forwards_extra_java_home_args() {
+        let java_home = stub_script(
+            &temp_dir("java-home-args"),
+            "java_home",
+            "echo \"$@\" >&2; echo '<plist version=\"1.0\"><array/></plist>'",
+        );
+        let args =
+            ["jvmvj", "list", "--java-home-args", "-v 17 -F"].map(String::from);
+        let extra_args = java_home_args(&args, Verbosity::Normal);
+        assert_eq!(extra_args, ["-v", "17", "-F"]);
+
+        let output =
+            run_java_home(&java_home, Duration::from_secs(5), &extra_args).unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "-X -v 17 -F\n");
+        assert!(output.jvms.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+//! `.jvmvjrc` files, which `auto` reads alongside the plain version files.
+//! Each is TOML with any of these fields:
+//!
+//! ```toml
+//! version = "temurin-17"  # a spec, used when no version file names one
+//! arch = "x86_64"         # only select JVMs of this architecture
+//! ```
+//!
+//! Every `.jvmvjrc` from the current directory upwards is read, and each
+//! field is taken from the nearest file that sets it. A parent can set `arch`
+//! for a whole tree while each project below it sets its own `version`.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{exit_with_err, Verbosity, Walk};
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rc {
+    pub version: Option<String>,
+    pub arch: Option<String>,
+}
+
+impl Rc {
+    /// This file's fields, falling back to `parent`'s where unset.
+    fn or(self, parent: Rc) -> Rc {
+        Rc {
+            version: self.version.or(parent.version),
+            arch: self.arch.or(parent.arch),
+        }
+    }
+}
+
+fn read(dir: &Path, verbosity: Verbosity) -> Option<Rc> {
+    let path = dir.join(".jvmvjrc");
+    let contents = fs::read_to_string(&path).ok()?;
+    trace!("found {}", path.display());
+    Some(toml::from_str(&contents).unwrap_or_else(|err| {
+        exit_with_err(
+            &format!("Failed to parse {}: {}", path.display(), err),
+            verbosity,
+        )
+    }))
+}
+
+/// The `.jvmvjrc` fields that apply in `dir`, merged nearest first.
+pub fn load(dir: &Path, walk: &Walk, verbosity: Verbosity) -> Rc {
+    let mut merged = Rc::default();
+    let mut dir = Some(dir);
+    while let Some(current) = dir {
+        if let Some(rc) = read(current, verbosity) {
+            merged = merged.or(rc);
+        }
+        if walk.stops_at(current) {
+            break;
+        }
+        dir = current.parent();
+    }
+    merged
+}
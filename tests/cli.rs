@@ -0,0 +1,1632 @@
+//! Runs the `jvmvj` binary against JVM homes made for each test, passing
+//! their `java_home -X` plist with `--plist-stdin` so that no real
+//! `java_home` is needed.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, SystemTime};
+
+/// A JVM to list in a fixture's plist.
+struct Jvm {
+    enabled: bool,
+    dir: &'static str,
+    version: &'static str,
+    arch: &'static str,
+    bundle_id: &'static str,
+    name: &'static str,
+    vendor: &'static str,
+    jdk: bool,
+}
+
+const TEMURIN_17: Jvm = Jvm {
+    enabled: true,
+    dir: "temurin-17.jdk",
+    version: "17.0.9",
+    arch: "x86_64",
+    bundle_id: "net.temurin.17.jdk",
+    name: "OpenJDK 17.0.9",
+    vendor: "Eclipse Adoptium",
+    jdk: true,
+};
+
+const ZULU_17: Jvm = Jvm {
+    enabled: true,
+    dir: "zulu-17.jdk",
+    version: "17.0.2",
+    arch: "x86_64",
+    bundle_id: "com.azul.zulu.17.jdk",
+    name: "Zulu 17",
+    vendor: "Azul Systems, Inc.",
+    jdk: true,
+};
+
+const TEMURIN_21: Jvm = Jvm {
+    enabled: true,
+    dir: "temurin-21.jdk",
+    version: "21.0.1",
+    arch: "x86_64",
+    bundle_id: "net.temurin.21.jdk",
+    name: "OpenJDK 21.0.1",
+    vendor: "Eclipse Adoptium",
+    jdk: true,
+};
+
+const TEMURIN_21_EA: Jvm = Jvm {
+    dir: "temurin-21-ea.jdk",
+    version: "21.0.2-ea",
+    bundle_id: "net.temurin.21.ea.jdk",
+    name: "OpenJDK 21.0.2-ea",
+    ..TEMURIN_21
+};
+
+/// A directory of JVM homes, a `HOME` for the config and cache, and a
+/// project directory to run in.
+struct Fixture {
+    root: PathBuf,
+    plist: String,
+}
+
+impl Fixture {
+    fn new(name: &str, jvms: &[Jvm]) -> Self {
+        let root = env::temp_dir().join(format!(
+            "jvmvj-cli-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("home")).unwrap();
+        fs::create_dir_all(root.join("project")).unwrap();
+        let mut plist = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\">\n<array>\n",
+        );
+        for jvm in jvms {
+            let home = root.join("jvms").join(jvm.dir).join("Contents/Home");
+            fs::create_dir_all(home.join("bin")).unwrap();
+            let tools = if jvm.jdk {
+                &["java", "javac"][..]
+            } else {
+                &["java"]
+            };
+            for tool in tools {
+                let path = home.join("bin").join(tool);
+                fs::write(&path, "").unwrap();
+                #[cfg(unix)]
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                    .unwrap();
+            }
+            fs::write(
+                home.join("release"),
+                format!(
+                    "JAVA_VERSION=\"{}\"\nIMPLEMENTOR=\"{}\"\nOS_ARCH=\"{}\"\n",
+                    jvm.version, jvm.vendor, jvm.arch
+                ),
+            )
+            .unwrap();
+            plist.push_str(&format!(
+                "<dict><key>JVMArch</key><string>{}</string><key>JVMBundleID</key><string>{}</string><key>JVMEnabled</key><{}/><key>JVMHomePath</key><string>{}</string><key>JVMName</key><string>{}</string><key>JVMPlatformVersion</key><string>{}</string><key>JVMVendor</key><string>{}</string><key>JVMVersion</key><string>{}</string></dict>\n",
+                jvm.arch,
+                jvm.bundle_id,
+                jvm.enabled,
+                home.display(),
+                jvm.name,
+                jvm.version,
+                jvm.vendor,
+                jvm.version
+            ));
+        }
+        plist.push_str("</array>\n</plist>\n");
+        Fixture { root, plist }
+    }
+
+    fn project(&self) -> PathBuf {
+        self.root.join("project")
+    }
+
+    fn home_of(&self, jvm: &Jvm) -> String {
+        self.root
+            .join("jvms")
+            .join(jvm.dir)
+            .join("Contents/Home")
+            .display()
+            .to_string()
+    }
+
+    /// Writes the config file read from the fixture's `HOME`.
+    fn config(&self, contents: &str) {
+        let dir = self.root.join("home/.config/jvmvj");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config.toml"), contents).unwrap();
+    }
+
+    /// Runs `jvmvj` with `args` in `dir`.
+    fn run_in(&self, dir: &Path, args: &[&str]) -> Output {
+        self.run_with(self.command(dir), args)
+    }
+
+    /// Runs `jvmvj` with `args` in the project directory, with `env` set.
+    fn run_env(&self, args: &[&str], env: &[(&str, &str)]) -> Output {
+        let mut command = self.command(&self.project());
+        command.envs(env.iter().copied());
+        self.run_with(command, args)
+    }
+
+    fn run_with(&self, mut command: Command, args: &[&str]) -> Output {
+        let mut child = command
+            .arg("--plist-stdin")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(self.plist.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    /// Runs `jvmvj` with `args` in the project directory, without the
+    /// fixture's JVMs, for commands that shouldn't need any.
+    fn run_without_jvms(&self, args: &[&str]) -> Output {
+        self.command(&self.project())
+            .args(args)
+            .env("STUB_FAIL", "1")
+            .stdin(Stdio::null())
+            .output()
+            .unwrap()
+    }
+
+    fn command(&self, dir: &Path) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_jvmvj"));
+        command
+            .current_dir(dir)
+            .env("HOME", self.root.join("home"))
+            .env_remove("JAVA_HOME")
+            .env_remove("CI")
+            .env_remove("JVMVJ_CONFIG")
+            .env_remove("JVMVJ_SPEC_PREFIX")
+            .env_remove("JVMVJ_JAVA_HOME_ARGS");
+        // CI runners set these for their own JDKs.
+        for (name, _) in env::vars_os() {
+            if name.to_string_lossy().starts_with("JAVA_HOME_") {
+                command.env_remove(name);
+            }
+        }
+        command
+    }
+
+    /// Runs `jvmvj` with `args` in the project directory.
+    fn run(&self, args: &[&str]) -> Output {
+        self.run_in(&self.project(), args)
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn quiet_auto_prints_only_the_path() {
+    let jre = Jvm {
+        jdk: false,
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("quiet", &[jre, ZULU_17]);
+    fs::write(fixture.project().join(".java-version"), "17\n").unwrap();
+
+    // --human and -vv would otherwise add a friendly line, traces, the
+    // ambiguity hint and the JRE warning.
+    for _ in 0..2 {
+        let output = fixture.run(&["auto", "--quiet", "--human", "-vv"]);
+        assert!(output.status.success());
+        assert_eq!(
+            stdout(&output),
+            format!("{}\n", fixture.home_of(&TEMURIN_17))
+        );
+        assert_eq!(stderr(&output), "");
+    }
+
+    fs::write(fixture.project().join(".java-version"), "99\n").unwrap();
+    let output = fixture.run(&["auto", "--quiet", "--human"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn takes_a_spec_after_double_dash_literally() {
+    let fixture = Fixture::new("double-dash", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["use", "--", "-zulu-17"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+
+    let output = fixture.run(&["use", "--", "--weird-distro-17"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn prunes_the_cache_without_java_home() {
+    let fixture = Fixture::new("prune", &[]);
+    let cache = fixture.root.join("home/.cache/jvmvj");
+    fs::create_dir_all(&cache).unwrap();
+    fs::write(cache.join("resolved.json"), "{}").unwrap();
+
+    let output = fixture.run_without_jvms(&["prune-cache"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "Freed 2 bytes\n");
+    assert!(!cache.join("resolved.json").exists());
+
+    let output = fixture.run_without_jvms(&["prune-cache"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "nothing to prune\n");
+}
+
+#[test]
+fn resolves_many_specs_like_resolve() {
+    let fixture = Fixture::new("resolve-many", &[TEMURIN_17, ZULU_17]);
+    let temurin = fixture.home_of(&TEMURIN_17);
+    let zulu = fixture.home_of(&ZULU_17);
+    let path_spec = format!("path:{}", zulu);
+
+    let output = fixture.run(&["resolve-many", "17", "99", "zulu-17", &path_spec]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "17\t{}\n99\tMISSING\nzulu-17\t{}\n{}\t{}\n",
+            temurin, zulu, path_spec, zulu
+        )
+    );
+
+    let output =
+        fixture.run(&["resolve-many", "17", "99", "zulu-17", "--fail-fast"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), format!("17\t{}\n99\tMISSING\n", temurin));
+
+    let output = fixture.run_env(
+        &["resolve-many", "current+", "path:/nonexistent"],
+        &[("JAVA_HOME", &zulu)],
+    );
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        stdout(&output),
+        format!("current+\t{}\npath:/nonexistent\tMISSING\n", temurin)
+    );
+
+    let output = fixture.run(&["resolve-many", "17", "zulu-17"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn lists_only_disabled_jvms() {
+    let disabled = Jvm {
+        enabled: false,
+        ..ZULU_17
+    };
+    let fixture = Fixture::new("disabled", &[TEMURIN_17, disabled]);
+    let homes = |args: &[&str]| {
+        let output = fixture.run(&[&["list", "--home"], args].concat());
+        assert!(output.status.success(), "{}", stderr(&output));
+        stdout(&output)
+    };
+    let temurin = format!("{}\n", fixture.home_of(&TEMURIN_17));
+    let zulu = format!("{}\n", fixture.home_of(&ZULU_17));
+    assert_eq!(homes(&["--disabled"]), zulu);
+    assert_eq!(homes(&["--enabled"]), temurin);
+    assert_eq!(homes(&[]), temurin);
+
+    for contradiction in [
+        ["--enabled", "--include-disabled"],
+        ["--enabled", "--disabled"],
+    ] {
+        let output = fixture.run(&[&["list"][..], &contradiction].concat());
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(stdout(&output), "");
+        assert!(
+            stderr(&output).contains("contradict"),
+            "{}",
+            stderr(&output)
+        );
+    }
+}
+
+#[test]
+fn matches_a_jvm_by_its_directory_name() {
+    let fixture = Fixture::new("dir-name", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["--dir-name", "zulu-17.jdk"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+
+    let output = fixture.run(&["--dir-name", "corretto-17.jdk"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains("corretto-17.jdk"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn reads_configured_version_files() {
+    let fixture = Fixture::new("version-files", &[TEMURIN_17, ZULU_17]);
+    fixture.config("version_files = [\".javaversion\", \".sdk-java\"]\n");
+    fs::write(fixture.project().join(".java-version"), "temurin-17\n").unwrap();
+    fs::write(fixture.project().join(".sdk-java"), "temurin-17\n").unwrap();
+    fs::write(fixture.project().join(".javaversion"), "zulu-17\n").unwrap();
+
+    let output = fixture.run(&["auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+}
+
+#[test]
+fn lists_in_a_markdown_table() {
+    let fixture = Fixture::new("markdown", &[TEMURIN_17]);
+    let output = fixture.run(&["list", "--style", "markdown"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let table = stdout(&output);
+    let lines = table.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 3, "{}", table);
+    assert!(lines.iter().all(|line| line.starts_with('|')), "{}", table);
+    assert!(lines[1].starts_with("|--"), "{}", table);
+    assert!(lines[2].contains("OpenJDK 17.0.9"), "{}", table);
+
+    let output = fixture.run(&["list", "--style", "fancy"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).contains("rounded, sharp, ascii, markdown, psql, blank"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn checks_for_a_matching_version_file_silently() {
+    let fixture = Fixture::new("check", &[TEMURIN_17]);
+    let outside = fixture.root.join("home");
+    let output = fixture.run_in(&outside, &["auto", "--check"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+
+    let module = fixture.project().join("module");
+    fs::create_dir_all(&module).unwrap();
+    fs::write(fixture.project().join(".java-version"), "17\n").unwrap();
+    let output = fixture.run_in(&module, &["auto", "--check"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+
+    fs::write(fixture.project().join(".java-version"), "21\n").unwrap();
+    let output = fixture.run_in(&module, &["auto", "--check"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn falls_back_through_tool_versions_specs() {
+    let fixture = Fixture::new("tool-versions", &[TEMURIN_17, ZULU_17]);
+    fs::write(
+        fixture.project().join(".tool-versions"),
+        "nodejs 20.11.0\njava temurin-21 zulu-17 temurin-17\n",
+    )
+    .unwrap();
+    let output = fixture.run(&["auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+}
+
+#[test]
+fn reports_jvms_without_an_executable_java() {
+    let fixture = Fixture::new("doctor", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["doctor"]);
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(stdout(&output), "2 healthy, 0 broken\n");
+
+    fs::remove_file(format!("{}/bin/java", fixture.home_of(&ZULU_17))).unwrap();
+    let output = fixture.run(&["doctor"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "Zulu 17 at {} has no executable bin/java\n1 healthy, 1 broken\n",
+            fixture.home_of(&ZULU_17)
+        )
+    );
+}
+
+#[test]
+fn lists_homes_separated_by_nul() {
+    let fixture = Fixture::new("print0", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["list", "--home", "--print0"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let nuls = output.stdout.iter().filter(|&&byte| byte == 0).count();
+    assert_eq!(nuls, 2);
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{}\0{}\0",
+            fixture.home_of(&TEMURIN_17),
+            fixture.home_of(&ZULU_17)
+        )
+    );
+}
+
+#[test]
+fn stops_at_the_git_root() {
+    let fixture = Fixture::new("git-root", &[TEMURIN_17, ZULU_17]);
+    let module = fixture.project().join("module");
+    fs::create_dir_all(&module).unwrap();
+    fs::create_dir_all(fixture.project().join(".git")).unwrap();
+    fs::write(fixture.root.join(".java-version"), "zulu-17\n").unwrap();
+
+    let output = fixture.run_in(&module, &["auto"]);
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+    let output = fixture.run_in(&module, &["auto", "--git-root"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+
+    fs::write(fixture.project().join(".java-version"), "temurin-17\n").unwrap();
+    let output = fixture.run_in(&module, &["auto", "--git-root"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+}
+
+#[test]
+fn warns_about_a_jre_or_skips_it_with_jdk_only() {
+    let jre = Jvm {
+        jdk: false,
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("jre", &[jre, ZULU_17]);
+    let output = fixture.run(&["use", "17"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+    assert!(
+        stderr(&output)
+            .contains("selected JVM appears to be a JRE; javac not found"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = fixture.run(&["use", "17", "--jdk-only"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+    assert!(!stderr(&output).contains("JRE"), "{}", stderr(&output));
+}
+
+#[test]
+fn remembers_a_used_spec_for_the_directory() {
+    let fixture = Fixture::new("remember", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["auto"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = fixture.run(&["use", "zulu-17", "--remember"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let dirs = fs::read_to_string(fixture.root.join("home/.cache/jvmvj/dirs.json"))
+        .unwrap();
+    let project = fs::canonicalize(fixture.project()).unwrap();
+    assert!(dirs.contains(&*project.to_string_lossy()), "{}", dirs);
+
+    let output = fixture.run(&["auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+    let output = fixture.run_in(&fixture.root.join("home"), &["auto"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn filters_the_list_by_vendor() {
+    let zulu_arm = Jvm {
+        dir: "zulu-17-arm.jdk",
+        arch: "arm64",
+        ..ZULU_17
+    };
+    let fixture = Fixture::new("vendor", &[TEMURIN_17, ZULU_17, zulu_arm]);
+    let zulu_arm = fixture.root.join("jvms/zulu-17-arm.jdk/Contents/Home");
+    let output = fixture.run(&["list", "--home", "--vendor", "azul"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n{}\n", fixture.home_of(&ZULU_17), zulu_arm.display())
+    );
+
+    let output =
+        fixture.run(&["list", "--home", "--vendor", "AZUL", "--arch", "arm64"]);
+    assert_eq!(stdout(&output), format!("{}\n", zulu_arm.display()));
+}
+
+#[test]
+fn execs_a_command_with_the_jvm() {
+    let fixture = Fixture::new("exec", &[TEMURIN_17, ZULU_17]);
+    let home = fixture.home_of(&TEMURIN_17);
+    let output = fixture.run(&[
+        "exec",
+        "17",
+        "--",
+        "/bin/sh",
+        "-c",
+        "echo $JAVA_HOME; command -v java",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n{}/bin/java\n", home, home));
+
+    let output = fixture.run(&["exec", "17", "--", "/bin/sh", "-c", "exit 7"]);
+    assert_eq!(output.status.code(), Some(7));
+}
+
+#[test]
+fn suggests_the_nearest_installed_majors() {
+    let temurin_21 = Jvm {
+        dir: "temurin-21.jdk",
+        version: "21.0.1",
+        bundle_id: "net.temurin.21.jdk",
+        name: "OpenJDK 21.0.1",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("suggest", &[TEMURIN_17, temurin_21]);
+    let output = fixture.run(&["use", "18"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stderr(&output), "18 not found; did you mean 17?\n");
+    let output = fixture.run(&["use", "19"]);
+    assert_eq!(stderr(&output), "19 not found; did you mean 17 or 21?\n");
+
+    let output = fixture.run(&["use", "19", "--quiet"]);
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn counts_listed_jvms_after_filters() {
+    let zulu_arm = Jvm {
+        dir: "zulu-17-arm.jdk",
+        arch: "arm64",
+        ..ZULU_17
+    };
+    let fixture = Fixture::new("count", &[TEMURIN_17, ZULU_17, zulu_arm]);
+    let count = |args: &[&str]| {
+        let output = fixture.run(&[&["list", "--count"], args].concat());
+        assert!(output.status.success(), "{}", stderr(&output));
+        stdout(&output)
+    };
+    assert_eq!(count(&[]), "3\n");
+    assert_eq!(count(&["--arch", "arm64"]), "1\n");
+    assert_eq!(count(&["--arch", "x86_64", "--vendor", "azul"]), "1\n");
+    assert_eq!(count(&["--arch", "riscv64"]), "0\n");
+}
+
+#[test]
+fn names_the_shell_function() {
+    let fixture = Fixture::new("init", &[TEMURIN_17]);
+    let output = fixture.run(&["init", "zsh", "--function-name", "jvm"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let script = stdout(&output);
+    assert!(script.contains("\njvm() {"), "{}", script);
+    assert!(
+        script.contains("add-zsh-hook chpwd _jvm_cd_hook"),
+        "{}",
+        script
+    );
+    assert!(!script.contains("jdk"), "{}", script);
+
+    let output = fixture.run(&["init", "zsh", "--function-name", "my-jvm"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn reports_failures_as_json() {
+    let fixture = Fixture::new("json-error", &[TEMURIN_17]);
+    let output = fixture.run(&["use", "99", "--json"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stderr(&output), "");
+    let error: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        error,
+        serde_json::json!({ "error": "99 not found; did you mean 17?", "code": 3 })
+    );
+}
+
+#[test]
+fn selects_a_candidate_by_index() {
+    let corretto = Jvm {
+        dir: "corretto-17.jdk",
+        version: "17.0.5",
+        bundle_id: "com.amazon.corretto.17.jdk",
+        name: "Amazon Corretto 17",
+        vendor: "Amazon.com Inc.",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("select-index", &[ZULU_17, corretto, TEMURIN_17]);
+    let output = fixture.run(&["use", "17", "--select-index", "1"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{}\n",
+            fixture
+                .root
+                .join("jvms/corretto-17.jdk/Contents/Home")
+                .display()
+        )
+    );
+
+    let output = fixture.run(&["use", "17", "--select-index", "3"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains("--select-index 3 is out of range"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn groups_the_list_by_vendor() {
+    let fixture = Fixture::new("group-by", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["list", "--group-by", "vendor"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let list = stdout(&output);
+    let headers = list
+        .lines()
+        .filter(|line| !line.starts_with(['╭', '│', '├', '╰']))
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        headers,
+        ["Azul Systems, Inc.", "Eclipse Adoptium"],
+        "{}",
+        list
+    );
+    let (azul, adoptium) = list.split_once("Eclipse Adoptium\n").unwrap();
+    assert!(azul.contains("Zulu 17"), "{}", list);
+    assert!(!azul.contains("OpenJDK"), "{}", list);
+    assert!(adoptium.contains("OpenJDK 17.0.9"), "{}", list);
+}
+
+#[test]
+fn prints_nothing_quietly_when_already_active() {
+    let fixture = Fixture::new("already-active", &[TEMURIN_17]);
+    let home = fixture.home_of(&TEMURIN_17);
+    let output = fixture.run_env(&["use", "17", "--quiet"], &[("JAVA_HOME", &home)]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+
+    let output = fixture.run(&["use", "17", "--quiet"]);
+    assert_eq!(stdout(&output), format!("{}\n", home));
+}
+
+#[test]
+fn prints_less_at_each_verbosity_level() {
+    let fixture = Fixture::new("verbosity", &[TEMURIN_17]);
+    let home = format!("{}\n", fixture.home_of(&TEMURIN_17));
+    let output = fixture.run(&["use", "17"]);
+    assert_eq!(stdout(&output), home);
+    assert_eq!(stderr(&output), "Activating Java OpenJDK 17.0.9\n");
+    let output = fixture.run(&["use", "99"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stderr(&output), "99 not found; did you mean 17?\n");
+
+    for level in ["--quiet", "--silent"] {
+        let output = fixture.run(&["use", "17", level]);
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), home);
+        assert_eq!(stderr(&output), "");
+    }
+
+    let output = fixture.run(&["use", "99", "--quiet"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+    let output = fixture.run(&["use", "99", "--silent"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn treats_a_generic_java_prefix_as_no_distro() {
+    // Named so that neither `java` nor `openjdk` could match it as a distro.
+    let temurin = Jvm {
+        name: "Temurin 17",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("java-prefix", &[temurin]);
+    for spec in ["java-17", "openjdk-17"] {
+        fs::write(fixture.project().join(".java-version"), spec).unwrap();
+        let output = fixture.run(&["auto"]);
+        assert!(output.status.success(), "{}: {}", spec, stderr(&output));
+        assert_eq!(
+            stdout(&output),
+            format!("{}\n", fixture.home_of(&TEMURIN_17))
+        );
+    }
+}
+
+#[test]
+fn describes_the_current_jvm_as_json() {
+    let fixture = Fixture::new("current-json", &[TEMURIN_17]);
+    let current = |java_home: Option<&str>| {
+        let output = match java_home {
+            Some(java_home) => {
+                fixture.run_env(&["current", "--json"], &[("JAVA_HOME", java_home)])
+            }
+            None => fixture.run(&["current", "--json"]),
+        };
+        assert!(output.status.success(), "{}", stderr(&output));
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let home = fixture.home_of(&TEMURIN_17);
+    assert_eq!(
+        current(Some(&home)),
+        serde_json::json!({
+            "java_home": home,
+            "name": "OpenJDK 17.0.9",
+            "version": "17.0.9",
+            "major": 17,
+            "managed": true,
+        })
+    );
+    assert_eq!(
+        current(Some("/opt/java")),
+        serde_json::json!({
+            "java_home": "/opt/java",
+            "name": null,
+            "version": null,
+            "major": null,
+            "managed": false,
+        })
+    );
+    assert_eq!(current(None)["java_home"], serde_json::Value::Null);
+    assert_eq!(current(None)["managed"], false);
+}
+
+#[test]
+fn selects_the_highest_version_matching_a_regex() {
+    let temurin_17_1 = Jvm {
+        dir: "temurin-17.1.jdk",
+        version: "17.1.0",
+        ..TEMURIN_17
+    };
+    let fixture =
+        Fixture::new("version-regex", &[ZULU_17, temurin_17_1, TEMURIN_17]);
+    let output = fixture.run(&["--version-regex", r"^17\.0\.[0-9]+$"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+
+    let output = fixture.run(&["--version-regex", "17.("]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).starts_with("Invalid version regex 17.("),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn finds_the_home_inside_a_bundle_path() {
+    let fixture = Fixture::new("bundle-path", &[ZULU_17]);
+    let bundle = fixture.root.join("jvms/zulu-17.jdk");
+    fs::write(
+        fixture.project().join(".tool-versions"),
+        format!("java path:{}\n", bundle.display()),
+    )
+    .unwrap();
+    let output = fixture.run(&["auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+
+    let output =
+        fixture.run(&["use", &format!("path:{}", fixture.home_of(&ZULU_17))]);
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+}
+
+#[test]
+fn prints_one_friendly_line_for_humans() {
+    let fixture = Fixture::new("human", &[TEMURIN_17]);
+    let output = fixture.run(&["use", "17", "--human"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "Activated OpenJDK 17.0.9 at {}\n",
+            fixture.home_of(&TEMURIN_17)
+        )
+    );
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn uses_java_home_variables_in_ci() {
+    let fixture = Fixture::new("ci", &[TEMURIN_17]);
+    let hosted = fixture
+        .root
+        .join("hostedtoolcache/Java_Temurin-Hotspot_jdk/17");
+    fs::create_dir_all(hosted.join("bin")).unwrap();
+    fs::write(hosted.join("bin/java"), "").unwrap();
+    let hosted = hosted.display().to_string();
+
+    let output = fixture
+        .run_env(&["use", "17"], &[("CI", "true"), ("JAVA_HOME_17", &hosted)]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", hosted));
+
+    let output = fixture.run_env(&["use", "17"], &[("JAVA_HOME_17", &hosted)]);
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+}
+
+#[test]
+fn prints_the_bin_directory() {
+    let fixture = Fixture::new("bin", &[TEMURIN_17]);
+    let home = fixture.home_of(&TEMURIN_17);
+    let output = fixture.run(&["bin", "17"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}/bin\n", home));
+
+    fs::remove_dir_all(format!("{}/bin", home)).unwrap();
+    let output = fixture.run(&["bin", "17"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(
+        stderr(&output),
+        format!("OpenJDK 17.0.9 has no bin directory at {}/bin\n", home)
+    );
+}
+
+#[test]
+fn never_selects_an_excluded_distro() {
+    let oracle = Jvm {
+        dir: "jdk-17.jdk",
+        version: "17.0.10",
+        bundle_id: "com.oracle.java.17.jdk",
+        name: "Java SE 17.0.10",
+        vendor: "Oracle Corporation",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("exclude", &[oracle, TEMURIN_17]);
+    fixture.config("exclude_distros = [\"oracle\"]\n");
+    let oracle = fixture.root.join("jvms/jdk-17.jdk/Contents/Home");
+
+    let output = fixture.run(&["use", "oracle-17"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+    let output = fixture.run(&["use", "17"]);
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+    let output = fixture.run(&["use", "oracle-17", "--include-excluded"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", oracle.display()));
+}
+
+#[test]
+fn resolves_a_spec_to_its_version() {
+    let fixture = Fixture::new("resolve", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["resolve", "zulu-17"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "17.0.2\n");
+    assert_eq!(stderr(&output), "");
+
+    let output = fixture.run(&["resolve", "21"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn sorts_the_list_by_install_time() {
+    let removed = Jvm {
+        dir: "removed-17.jdk",
+        version: "17.0.12",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("sort-installed", &[TEMURIN_17, removed, ZULU_17]);
+    fs::remove_dir_all(fixture.root.join("jvms/removed-17.jdk")).unwrap();
+    let removed = fixture.root.join("jvms/removed-17.jdk/Contents/Home");
+    let installed = |jvm: &Jvm, secs: u64| {
+        fs::File::open(fixture.home_of(jvm))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    };
+    installed(&TEMURIN_17, 1_600_000_000);
+    installed(&ZULU_17, 1_700_000_000);
+
+    let output = fixture.run(&["list", "--home", "--sort", "installed"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{}\n{}\n{}\n",
+            fixture.home_of(&ZULU_17),
+            fixture.home_of(&TEMURIN_17),
+            removed.display()
+        )
+    );
+}
+
+#[test]
+fn hides_jvms_whose_home_is_missing() {
+    let fixture = Fixture::new("installed-only", &[TEMURIN_17, ZULU_17]);
+    fs::remove_dir_all(fixture.root.join("jvms").join(ZULU_17.dir)).unwrap();
+    let temurin = fixture.home_of(&TEMURIN_17);
+    let zulu = fixture.home_of(&ZULU_17);
+
+    let output = fixture.run(&["list", "--home"]);
+    assert_eq!(stdout(&output), format!("{}\n{}\n", temurin, zulu));
+    let output = fixture.run(&["list", "--home", "--installed-only"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", temurin));
+}
+
+#[test]
+fn pins_the_porcelain_format() {
+    let fixture = Fixture::new("porcelain", &[TEMURIN_17, ZULU_17]);
+    let temurin = fixture.home_of(&TEMURIN_17);
+    let zulu = fixture.home_of(&ZULU_17);
+    let output = fixture.run(&["list", "--porcelain"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "# jvmvj porcelain v1\n\
+             V\t17.0.9\tOpenJDK 17.0.9\tx86_64\t{}\n\
+             V\t17.0.2\tZulu 17\tx86_64\t{}\n",
+            temurin, zulu
+        )
+    );
+
+    let output =
+        fixture.run_env(&["current", "--porcelain"], &[("JAVA_HOME", &zulu)]);
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "# jvmvj porcelain v1\nV\t17.0.2\tZulu 17\tx86_64\t{}\n",
+            zulu
+        )
+    );
+    let output = fixture.run(&["current", "--porcelain"]);
+    assert_eq!(stdout(&output), "# jvmvj porcelain v1\n");
+}
+
+#[test]
+fn runs_the_post_switch_command_when_the_home_changes() {
+    let fixture = Fixture::new("post-switch", &[TEMURIN_17]);
+    let sentinel = fixture.root.join("switched");
+    fixture.config(&format!(
+        "post_switch = \"echo $JVMVJ_JAVA_HOME > '{}'\"\n",
+        sentinel.display()
+    ));
+    let home = fixture.home_of(&TEMURIN_17);
+
+    let output = fixture.run(&["use", "17"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", home));
+    assert_eq!(
+        fs::read_to_string(&sentinel).unwrap(),
+        format!("{}\n", home)
+    );
+
+    fs::remove_file(&sentinel).unwrap();
+    let output = fixture.run_env(&["use", "17"], &[("JAVA_HOME", &home)]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!sentinel.exists());
+}
+
+#[test]
+fn normalizes_arches_for_the_arch_filter() {
+    let zulu_arm = Jvm {
+        arch: "aarch64",
+        ..ZULU_17
+    };
+    let fixture = Fixture::new("arch-alias", &[TEMURIN_17, zulu_arm]);
+    let zulu = format!("{}\n", fixture.home_of(&ZULU_17));
+    for arch in ["arm", "arm64", "aarch64"] {
+        let output = fixture.run(&["list", "--home", "--arch", arch]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), zulu, "--arch {}", arch);
+    }
+
+    let output = fixture.run(&["list", "--json", "--arch", "arm"]);
+    let jvms: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(jvms[0]["arch"], "arm64");
+
+    fixture.config("[arch_aliases]\napple = \"arm64\"\n");
+    let output = fixture.run(&["list", "--home", "--arch", "apple"]);
+    assert_eq!(stdout(&output), zulu);
+}
+
+#[test]
+fn limits_the_list_to_the_first_rows() {
+    let temurin_21 = Jvm {
+        dir: "temurin-21.jdk",
+        version: "21.0.1",
+        name: "OpenJDK 21.0.1",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("limit", &[TEMURIN_17, temurin_21, ZULU_17]);
+    let output = fixture.run(&["list", "--limit", "2", "--style", "markdown"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let table = stdout(&output);
+    let rows = table.lines().skip(2).collect::<Vec<&str>>();
+    assert_eq!(rows.len(), 2, "{}", table);
+    assert!(rows[0].contains("21.0.1"), "{}", table);
+    assert!(rows[1].contains("17.0.9"), "{}", table);
+
+    for limit in ["0", "-1"] {
+        let output = fixture.run(&["list", "--limit", limit]);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(stdout(&output), "");
+    }
+}
+
+#[test]
+fn upgrades_to_the_newest_patch_of_the_current_major() {
+    let older = Jvm {
+        dir: "temurin-17.0.5.jdk",
+        version: "17.0.5",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("current-plus", &[older, TEMURIN_17]);
+    let older = fixture.root.join("jvms/temurin-17.0.5.jdk/Contents/Home");
+    let output = fixture.run_env(
+        &["use", "current+"],
+        &[("JAVA_HOME", &older.display().to_string())],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+
+    let output =
+        fixture.run_env(&["use", "current+"], &[("JAVA_HOME", "/opt/java")]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains("current+ needs JAVA_HOME"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn traces_discovery_with_vv() {
+    let fixture = Fixture::new("trace", &[TEMURIN_17]);
+    let version_file = fixture.project().join(".java-version");
+    fs::write(&version_file, "17\n").unwrap();
+
+    let output = fixture.run(&["auto"]);
+    assert_eq!(stderr(&output), "Activating Java OpenJDK 17.0.9\n");
+
+    let output = fixture.run(&["auto", "-vv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+    let traces = stderr(&output);
+    let version_file = fs::canonicalize(version_file).unwrap();
+    for trace in [
+        "jvmvj: stdin listed 1 JVMs".to_string(),
+        format!("jvmvj: checking {}", version_file.display()),
+        format!("jvmvj: found spec \"17\" in {}", version_file.display()),
+    ] {
+        assert!(traces.lines().any(|line| line == trace), "{}", traces);
+    }
+}
+
+#[test]
+fn lists_only_one_major() {
+    let temurin_21 = Jvm {
+        dir: "temurin-21.jdk",
+        version: "21.0.1",
+        name: "OpenJDK 21.0.1",
+        ..TEMURIN_17
+    };
+    let fixture = Fixture::new("only-major", &[TEMURIN_17, temurin_21, ZULU_17]);
+    let output = fixture.run(&["list", "--only-major", "17", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let jvms: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let versions = jvms
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|jvm| jvm["version"].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(versions, ["17.0.9", "17.0.2"]);
+}
+
+#[test]
+fn fails_auto_cleanly() {
+    let fixture = Fixture::new("auto-failures", &[TEMURIN_17]);
+    let output = fixture.run(&["auto"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(
+        stderr(&output),
+        "No version file (.java-version, .tool-versions, .sdkmanrc) found in this directory or any parent!\n"
+    );
+
+    fs::write(fixture.project().join(".java-version"), "21\n").unwrap();
+    let output = fixture.run(&["auto"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "21 not found; did you mean 17?\n");
+
+    fs::write(fixture.project().join(".java-version"), "1.x\n").unwrap();
+    let output = fixture.run(&["auto"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "Did not understand version spec 1.x\n");
+}
+
+#[test]
+fn lists_one_json_object_per_line() {
+    let fixture = Fixture::new("jsonl", &[TEMURIN_17, ZULU_17]);
+    let output = fixture.run(&["list", "--jsonl"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let lines = stdout(&output);
+    let jvms = lines
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<serde_json::Value>>();
+    assert_eq!(jvms.len(), 2);
+    assert_eq!(jvms[0]["version"], "17.0.9");
+    assert_eq!(jvms[1]["version"], "17.0.2");
+}
+
+#[test]
+fn migrates_tool_versions_to_java_version() {
+    let fixture = Fixture::new("migrate", &[]);
+    let tool_versions = fixture.project().join(".tool-versions");
+    let java_version = fixture.project().join(".java-version");
+
+    fs::write(&tool_versions, "nodejs 20.11.0\n").unwrap();
+    let output = fixture.run_without_jvms(&["migrate"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "No java entry found in .tool-versions!\n");
+    assert!(!java_version.exists());
+
+    fs::write(&tool_versions, "nodejs 20.11.0\njava temurin-17\n").unwrap();
+    let output = fixture.run_without_jvms(&["migrate"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(&java_version).unwrap(), "temurin-17\n");
+    assert_eq!(
+        fs::read_to_string(&tool_versions).unwrap(),
+        "nodejs 20.11.0\njava temurin-17\n"
+    );
+
+    fs::remove_file(&java_version).unwrap();
+    fs::write(&tool_versions, "java 17.0.9-tem\n").unwrap();
+    let output = fixture.run_without_jvms(&["migrate"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(&java_version).unwrap(), "temurin-17\n");
+}
+
+#[test]
+fn explains_how_auto_resolves() {
+    let fixture = Fixture::new("explain", &[TEMURIN_17, ZULU_17]);
+    let module = fixture.project().join("module");
+    fs::create_dir_all(&module).unwrap();
+    fs::write(fixture.project().join(".java-version"), "17\n").unwrap();
+    let version_file = fs::canonicalize(fixture.project())
+        .unwrap()
+        .join(".java-version");
+    let temurin = fixture.home_of(&TEMURIN_17);
+    let zulu = fixture.home_of(&ZULU_17);
+
+    let output = fixture.run_in(&module, &["explain"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "Spec from: {}\n\
+             Spec: 17\n\
+             Parsed: major 17, distro any\n  \
+             1. OpenJDK 17.0.9 (x86_64) at {}\n  \
+             2. Zulu 17 (x86_64) at {}\n\
+             Selected: OpenJDK 17.0.9 at {}\n",
+            version_file.display(),
+            temurin,
+            zulu,
+            temurin
+        )
+    );
+}
+
+#[test]
+fn loads_the_config_from_a_given_path() {
+    let fixture = Fixture::new("config-path", &[TEMURIN_17, ZULU_17]);
+    let config = fixture.root.join("custom.toml");
+    fs::write(
+        &config,
+        "default_distro = { 17 = \"zulu\" }\n[arch_aliases]\nintel = \"x86_64\"\n",
+    )
+    .unwrap();
+    let config = config.display().to_string();
+    let zulu = format!("{}\n", fixture.home_of(&ZULU_17));
+
+    let output = fixture.run(&["use", "17"]);
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+    let output = fixture.run(&["use", "17", "--config", &config]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), zulu);
+    let output = fixture.run_env(&["use", "17"], &[("JVMVJ_CONFIG", &config)]);
+    assert_eq!(stdout(&output), zulu);
+    let output =
+        fixture.run(&["list", "--count", "--arch", "intel", "--config", &config]);
+    assert_eq!(stdout(&output), "2\n");
+
+    let missing = fixture.root.join("missing.toml").display().to_string();
+    let output = fixture.run(&["use", "17", "--config", &missing]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output)
+            .starts_with(&format!("Failed to read config file {}", missing)),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn colors_the_table_only_when_asked() {
+    let fixture = Fixture::new("color", &[TEMURIN_17, ZULU_17]);
+    let home = fixture.home_of(&ZULU_17);
+    let table = |color: &str| {
+        let output =
+            fixture.run_env(&["list", "--color", color], &[("JAVA_HOME", &home)]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        stdout(&output)
+    };
+    assert!(!table("never").contains('\x1b'));
+    assert!(!table("auto").contains('\x1b'));
+    let colored = table("always");
+    assert!(colored.contains("\x1b[1m│ 17.0.9"), "{}", colored);
+    assert!(colored.contains("\x1b[32m│ 17.0.2"), "{}", colored);
+}
+
+#[test]
+fn prefers_ga_builds_unless_ea_is_allowed() {
+    let fixture = Fixture::new("allow-ea", &[TEMURIN_21_EA, TEMURIN_21]);
+    let output = fixture.run(&["use", "21"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_21))
+    );
+    let output = fixture.run(&["use", "21", "--allow-ea"]);
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_21_EA))
+    );
+
+    let fixture = Fixture::new("only-ea", &[TEMURIN_21_EA]);
+    let output = fixture.run(&["use", "21"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_21_EA))
+    );
+}
+
+#[test]
+fn describes_the_selected_jvm_as_json() {
+    let fixture = Fixture::new("use-json", &[TEMURIN_17]);
+    let output = fixture.run(&["use", "17", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let jvm: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        jvm,
+        serde_json::json!({
+            "home": fixture.home_of(&TEMURIN_17),
+            "name": "OpenJDK 17.0.9",
+            "version": "17.0.9",
+            "major": 17,
+            "arch": "x86_64",
+            "vendor": "Eclipse Adoptium",
+        })
+    );
+}
+
+#[test]
+fn prints_the_current_major_quietly() {
+    let fixture = Fixture::new("current-quiet", &[TEMURIN_17]);
+    let home = fixture.home_of(&TEMURIN_17);
+    let output = fixture.run_env(&["current", "--quiet"], &[("JAVA_HOME", &home)]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "17\n");
+
+    for env in [&[("JAVA_HOME", "/opt/java")][..], &[]] {
+        let output = fixture.run_env(&["current", "--quiet"], env);
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), "");
+        assert_eq!(stderr(&output), "");
+    }
+}
+
+#[test]
+fn renders_only_the_requested_fields_in_order() {
+    let fixture = Fixture::new("fields", &[TEMURIN_17]);
+    let output = fixture.run(&[
+        "list",
+        "--fields",
+        "version,vendor,home",
+        "--style",
+        "markdown",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let table = stdout(&output);
+    let cells = |line: &str| {
+        line.trim_matches('|')
+            .split('|')
+            .map(|cell| cell.trim().to_string())
+            .collect::<Vec<String>>()
+    };
+    let lines = table.lines().collect::<Vec<&str>>();
+    assert_eq!(cells(lines[0]), ["version", "vendor", "home"]);
+    assert_eq!(
+        cells(lines[2]),
+        ["17.0.9", "Eclipse Adoptium", &fixture.home_of(&TEMURIN_17)]
+    );
+
+    let output = fixture.run(&["list", "--fields", "version,colour"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).starts_with(
+            "Unknown field colour! Valid fields are: version, name, arch, vendor, home, bundle_id, platform_version, enabled"
+        ),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn prints_every_field_of_the_selected_jvm() {
+    let fixture = Fixture::new("info", &[TEMURIN_17]);
+    let output = fixture.run(&["info", "17"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let info = stdout(&output);
+    let home = format!("home_path:        {}", fixture.home_of(&TEMURIN_17));
+    for line in [
+        "arch:             x86_64",
+        "bundle_id:        net.temurin.17.jdk",
+        "enabled:          true",
+        &home,
+        "vendor:           Eclipse Adoptium",
+        "version:          17.0.9",
+        "major:            17",
+        "update:           9",
+    ] {
+        assert!(info.lines().any(|info| info == line), "{}", info);
+    }
+
+    let output = fixture.run(&["info", "21"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn selects_only_early_access_builds_for_an_ea_spec() {
+    let fixture = Fixture::new("ea-spec", &[TEMURIN_21, TEMURIN_21_EA]);
+    let output = fixture.run(&["use", "21-ea"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_21_EA))
+    );
+
+    let fixture = Fixture::new("ea-spec-ga-only", &[TEMURIN_21]);
+    let output = fixture.run(&["use", "21-ea"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn lists_a_plist_piped_on_stdin() {
+    let fixture = Fixture::new("plist-stdin", &[]);
+    let list = |stdin: Stdio| {
+        fixture
+            .command(&fixture.project())
+            .args(["--plist-stdin", "list", "--fields", "version,name,arch"])
+            .env("STUB_FAIL", "1")
+            .stdin(stdin)
+            .output()
+            .unwrap()
+    };
+    let plist = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("benches/fixtures/java_home.plist");
+    let output = list(fs::File::open(plist).unwrap().into());
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "\
+╭───────────┬───────────────────────────┬────────╮
+│ version   │ name                      │ arch   │
+├───────────┼───────────────────────────┼────────┤
+│ 21.0.5    │ OpenJDK 21.0.5            │ arm64  │
+│ 21.0.5    │ Oracle GraalVM 21.0.5+9.1 │ arm64  │
+│ 17.0.13   │ OpenJDK 17.0.13           │ arm64  │
+│ 17.0.13   │ Zulu 17.54.21             │ x86_64 │
+│ 11.0.25   │ OpenJDK 11.0.25           │ arm64  │
+│ 1.8.0_432 │ Zulu 8.82.0.21            │ x86_64 │
+╰───────────┴───────────────────────────┴────────╯
+"
+    );
+
+    let output = list(Stdio::null());
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "--plist-stdin was given but stdin is empty!\n"
+    );
+
+    let garbage = fixture.root.join("garbage");
+    fs::write(&garbage, "garbage\n").unwrap();
+    let output = list(fs::File::open(garbage).unwrap().into());
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).starts_with("Failed to parse the plist on stdin"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn reads_only_the_named_version_file() {
+    let fixture = Fixture::new("only", &[TEMURIN_17]);
+    fs::write(fixture.project().join(".tool-versions"), "java 17\n").unwrap();
+    let output = fixture.run(&["auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = fixture.run(&["auto", "--only", "java-version"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(
+        stderr(&output),
+        "No version file (.java-version) found in this directory or any parent!\n"
+    );
+
+    fs::write(fixture.root.join(".java-version"), "17\n").unwrap();
+    let output = fixture.run(&["auto", "--only", "java-version"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn restricts_a_spec_to_its_arch_suffix() {
+    let zulu_arm = Jvm {
+        dir: "zulu-17-arm.jdk",
+        arch: "aarch64",
+        ..ZULU_17
+    };
+    let fixture = Fixture::new("arch-suffix", &[TEMURIN_17, zulu_arm]);
+    let zulu_arm = fixture.root.join("jvms/zulu-17-arm.jdk/Contents/Home");
+    let output = fixture.run(&["use", "17"]);
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+    let output = fixture.run(&["use", "17@aarch64"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", zulu_arm.display()));
+
+    let output = fixture.run(&["use", "temurin-17@x86_64"]);
+    assert_eq!(
+        stdout(&output),
+        format!("{}\n", fixture.home_of(&TEMURIN_17))
+    );
+    let output = fixture.run(&["use", "temurin-17@aarch64"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn previews_a_pin_with_dry_run() {
+    let fixture = Fixture::new("pin", &[TEMURIN_21]);
+    let path = fs::canonicalize(fixture.project())
+        .unwrap()
+        .join(".java-version");
+    let output = fixture.run(&["pin", "21", "--dry-run"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "Would write 21 to {}, selecting OpenJDK 21.0.1\n",
+            path.display()
+        )
+    );
+    assert!(!path.exists());
+
+    let output = fixture.run(&["pin", "21"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "21\n");
+}
+
+#[test]
+fn hints_when_several_jvms_of_a_major_match() {
+    let fixture = Fixture::new("ambiguous", &[TEMURIN_17, ZULU_17]);
+    fs::write(fixture.project().join(".java-version"), "17\n").unwrap();
+    let output = fixture.run(&["auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let hints = stderr(&output)
+        .lines()
+        .filter(|line| line.starts_with("Hint: "))
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+    assert_eq!(
+        hints,
+        ["Hint: 2 JVMs match 17 (OpenJDK 17.0.9, Zulu 17); set distro_priority or default_distro in the config, or name a distro in the version file, to choose one"]
+    );
+
+    fixture.config("distro_priority = [\"zulu\"]\n");
+    let output = fixture.run(&["auto"]);
+    assert_eq!(stdout(&output), format!("{}\n", fixture.home_of(&ZULU_17)));
+    assert_eq!(stderr(&output), "Activating Java Zulu 17\n");
+
+    fs::write(fixture.project().join(".java-version"), "temurin-17\n").unwrap();
+    fixture.config("");
+    let output = fixture.run(&["auto"]);
+    assert_eq!(stderr(&output), "Activating Java OpenJDK 17.0.9\n");
+}
+
+#[test]
+fn works_without_a_home() {
+    let fixture = Fixture::new("no-home", &[TEMURIN_17]);
+    fs::write(fixture.project().join(".java-version"), "17\n").unwrap();
+    let home = format!("{}\n", fixture.home_of(&TEMURIN_17));
+    let without_home = |unset: bool, args: &[&str]| {
+        let mut command = fixture.command(&fixture.project());
+        if unset {
+            command.env_remove("HOME");
+        } else {
+            command.env("HOME", "");
+        }
+        fixture.run_with(command, args)
+    };
+
+    for unset in [true, false] {
+        let output = without_home(unset, &["auto"]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), home);
+
+        let output = without_home(unset, &["use", "17", "--remember"]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), home);
+        assert_eq!(
+            stderr(&output),
+            "Activating Java OpenJDK 17.0.9\nNot remembering this choice as HOME is not set\n"
+        );
+
+        let output = without_home(unset, &["list", "--count"]);
+        assert_eq!(stdout(&output), "1\n");
+    }
+    // The cache isn't kept relative to the current directory instead.
+    assert!(!fixture.project().join(".cache").exists());
+}